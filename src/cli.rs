@@ -6,6 +6,7 @@ mod image;
 mod install;
 mod k8s;
 mod kube;
+mod lenient;
 mod network;
 mod pod;
 pub mod service;
@@ -17,6 +18,7 @@ mod systemd_dbus;
 
 use std::{
     borrow::Cow,
+    collections::HashSet,
     env,
     ffi::OsStr,
     fmt::{self, Display},
@@ -147,14 +149,18 @@ impl Cli {
         if self.unit_directory || self.file.is_some() {
             let path = self.file_path()?;
             if matches!(path, FilePath::Full(..))
-                && matches!(self.command, Commands::Compose { .. })
+                && matches!(
+                    self.command,
+                    Commands::Compose { .. } | Commands::Kube { .. }
+                )
             {
                 return Err(eyre!(
-                    "A file path was provided to `--file` and the `compose` command was used"
+                    "A file path was provided to `--file` and the `compose` or `kube` command was \
+                        used"
                 )
                 .suggestion(
                     "Provide a directory to `--file`. \
-                        `compose` can generate multiple files so a directory is needed.",
+                        `compose` and `kube` can generate multiple files so a directory is needed.",
                 ));
             }
 
@@ -162,7 +168,15 @@ impl Cli {
             #[cfg(unix)]
             let services_check = !self.skip_services_check;
 
-            let files = self.try_into_files()?;
+            let output_dir = match &path {
+                FilePath::Full(path) => path.parent().unwrap_or(Path::new("")),
+                FilePath::Dir(path) => path,
+            };
+            let output_dir = absolute_clean_path(
+                &env::current_dir().wrap_err("current working directory could not be read")?,
+                output_dir,
+            );
+            let files = self.try_into_files(Some(&output_dir))?;
 
             #[cfg(unix)]
             if services_check {
@@ -180,9 +194,9 @@ impl Cli {
             Ok(())
         } else {
             let files = self
-                .try_into_files()?
+                .try_into_files(None)?
                 .into_iter()
-                .map(|file| format!("# {}.{}\n{file}", file.name(), file.extension()))
+                .map(|file| format!("# {}\n{file}", file.file_name()))
                 .collect::<Vec<_>>()
                 .join("\n---\n\n");
             print!("{files}");
@@ -248,11 +262,16 @@ impl Cli {
                     );
                     Ok(path)
                 } else {
-                    match &self.command {
-                        Commands::Compose(Compose {
-                            compose_file: Some(path),
-                            ..
-                        }) if path.as_os_str() != "-" && !path.as_os_str().is_empty() => {
+                    let compose_file = match &self.command {
+                        // Relative paths in compose files are resolved from the directory of the
+                        // first compose file.
+                        Commands::Compose(Compose { compose_file, .. }) => compose_file
+                            .first()
+                            .filter(|path| path.as_os_str() != "-" && !path.as_os_str().is_empty()),
+                        _ => None,
+                    };
+                    match compose_file {
+                        Some(path) => {
                             if let Some(path) = path.parent() {
                                 let current_dir = env::current_dir().wrap_err(CURRENT_DIR_ERR)?;
                                 Ok(absolute_clean_path(&current_dir, path))
@@ -261,7 +280,7 @@ impl Cli {
                                 Ok(path.to_owned())
                             }
                         }
-                        _ => env::current_dir().wrap_err(CURRENT_DIR_ERR),
+                        None => env::current_dir().wrap_err(CURRENT_DIR_ERR),
                     }
                 }
             })
@@ -269,7 +288,9 @@ impl Cli {
     }
 
     /// Convert into [`File`]s
-    fn try_into_files(mut self) -> color_eyre::Result<Vec<File>> {
+    ///
+    /// `output_dir` is the directory the files are written to, [`None`] if they are printed.
+    fn try_into_files(mut self, output_dir: Option<&Path>) -> color_eyre::Result<Vec<File>> {
         let resolve_dir = self
             .resolve_dir()
             .wrap_err("error with `--absolute-host-paths` resolve directory")?;
@@ -279,11 +300,19 @@ impl Cli {
 
         let mut files = self.command.try_into_files(self.name, unit, install)?;
 
+        // Files which are not Quadlet files, e.g. Kubernetes YAML, are written next to the Quadlet
+        // files which reference them.
+        let generated: HashSet<PathBuf> = files
+            .iter()
+            .filter(|file| file.as_quadlet_file().is_none())
+            .map(|file| file.file_name().into())
+            .collect();
+
         let downgrade = self.podman_version < PodmanVersion::LATEST;
         if downgrade || resolve_dir.is_some() {
             for file in &mut files {
                 if let Some(resolve_dir) = &resolve_dir {
-                    file.absolutize_host_paths(resolve_dir);
+                    file.absolutize_host_paths(resolve_dir, &generated, output_dir);
                 }
 
                 if downgrade {
@@ -317,11 +346,7 @@ impl FilePath {
     fn to_full(&self, file: &File) -> Cow<Path> {
         match self {
             Self::Full(path) => path.into(),
-            Self::Dir(path) => {
-                let mut path = path.join(file.name());
-                path.set_extension(file.extension());
-                path.into()
-            }
+            Self::Dir(path) => path.join(file.file_name()).into(),
         }
     }
 }
@@ -348,7 +373,8 @@ enum Commands {
     /// Some compose options are not supported, such as `build`.
     ///
     /// When Podlet encounters an unsupported option, an error will be returned.
-    /// Modify the compose file to resolve the error.
+    /// Modify the compose file to resolve the error, or use `--lenient` to drop service fields which
+    /// are not supported at all.
     Compose(#[command(flatten)] Compose),
 
     /// Generate a Podman Quadlet file from an existing object.
@@ -357,6 +383,10 @@ enum Commands {
     /// from the `PATH` environment variable.
     #[command(subcommand)]
     Generate(Generate),
+
+    /// Generate Podman Quadlet files from Kubernetes YAML
+    #[command(subcommand)]
+    Kube(KubeCommands),
 }
 
 impl Commands {
@@ -387,10 +417,32 @@ impl Commands {
                 .into_iter()
                 .map(Into::into)
                 .collect()),
+            Self::Kube(KubeCommands::Import(import)) => Ok(import
+                .try_into_quadlet_files(unit.as_ref(), install.as_ref())
+                .wrap_err("error importing Kubernetes YAML")?
+                .into_iter()
+                .map(Into::into)
+                .collect()),
         }
     }
 }
 
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+enum KubeCommands {
+    /// Convert Kubernetes YAML into native Podman Quadlet files
+    ///
+    /// Creates a `.pod` file for each pod or deployment,
+    /// a `.container` file for each of its containers,
+    /// and a `.volume` file for each persistent volume claim.
+    ///
+    /// Unlike `podlet podman kube`, the Kubernetes YAML file is not needed at runtime.
+    ///
+    /// Fields which could not be converted are reported and dropped.
+    ///
+    /// The `--file` option must be a directory if used.
+    Import(k8s::Import),
+}
+
 #[allow(clippy::doc_markdown)]
 #[derive(Subcommand, Debug, Clone, PartialEq)]
 enum PodmanCommands {
@@ -517,6 +569,7 @@ impl PodmanCommands {
 enum File {
     Quadlet(quadlet::File),
     Kubernetes(k8s::File),
+    Generated(GeneratedFile),
 }
 
 impl From<quadlet::File> for File {
@@ -531,11 +584,18 @@ impl From<k8s::File> for File {
     }
 }
 
+impl From<GeneratedFile> for File {
+    fn from(value: GeneratedFile) -> Self {
+        Self::Generated(value)
+    }
+}
+
 impl Display for File {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Quadlet(file) => file.fmt(f),
             Self::Kubernetes(file) => file.fmt(f),
+            Self::Generated(file) => f.write_str(&file.content),
         }
     }
 }
@@ -545,21 +605,33 @@ impl File {
         match self {
             Self::Quadlet(file) => &file.name,
             Self::Kubernetes(file) => &file.name,
+            Self::Generated(file) => &file.name,
         }
     }
 
-    fn extension(&self) -> &str {
+    /// The file's extension, [`None`] if the extension is part of the [name](Self::name()).
+    fn extension(&self) -> Option<&str> {
         match self {
-            Self::Quadlet(file) => file.resource.extension(),
-            Self::Kubernetes(_) => "yaml",
+            Self::Quadlet(file) => Some(file.resource.extension()),
+            Self::Kubernetes(_) => Some("yaml"),
+            Self::Generated(_) => None,
         }
     }
 
+    /// The full file name, including the extension.
+    fn file_name(&self) -> String {
+        let name = self.name();
+        self.extension().map_or_else(
+            || name.to_owned(),
+            |extension| format!("{name}.{extension}"),
+        )
+    }
+
     /// Returns [`Some`] if a [`File::Quadlet`].
     fn as_quadlet_file(&self) -> Option<&quadlet::File> {
         match self {
             Self::Quadlet(file) => Some(file),
-            Self::Kubernetes(_) => None,
+            Self::Kubernetes(_) | Self::Generated(_) => None,
         }
     }
 
@@ -567,16 +639,29 @@ impl File {
     fn as_quadlet_file_mut(&mut self) -> Option<&mut quadlet::File> {
         match self {
             Self::Quadlet(file) => Some(file),
-            Self::Kubernetes(_) => None,
+            Self::Kubernetes(_) | Self::Generated(_) => None,
         }
     }
 
     /// If a Quadlet file, make all host paths absolute and clean.
     ///
-    /// Relative paths are resolved using `resolve_dir` as the base.
-    fn absolutize_host_paths(&mut self, resolve_dir: &Path) {
+    /// Relative paths are resolved using `resolve_dir` as the base. Paths to `generated` files are
+    /// resolved from the `output_dir` they are written to, or left relative if [`None`], as Quadlet
+    /// resolves relative paths from the directory of the Quadlet file.
+    fn absolutize_host_paths(
+        &mut self,
+        resolve_dir: &Path,
+        generated: &HashSet<PathBuf>,
+        output_dir: Option<&Path>,
+    ) {
         for path in self.host_paths() {
-            *path = absolute_clean_path(resolve_dir, path);
+            if generated.contains(path.strip_prefix(".").unwrap_or(path)) {
+                if let Some(output_dir) = output_dir {
+                    *path = absolute_clean_path(output_dir, path);
+                }
+            } else {
+                *path = absolute_clean_path(resolve_dir, path);
+            }
         }
     }
 
@@ -592,6 +677,17 @@ impl File {
     }
 }
 
+/// A generated file which is placed alongside the Quadlet files, e.g. for the content of a compose
+/// config.
+#[derive(Debug)]
+struct GeneratedFile {
+    /// The full name of the file, including any extension.
+    name: String,
+
+    /// The contents of the file.
+    content: String,
+}
+
 /// If `path` is relative, it is resolved using `resolve_dir` and a cleaned version is returned.
 fn absolute_clean_path(resolve_dir: &Path, path: &Path) -> PathBuf {
     // Paths starting with "%" are also absolute because they start with a systemd specifier.
@@ -640,7 +736,7 @@ impl Downgrade for File {
     fn downgrade(&mut self, version: PodmanVersion) -> Result<(), DowngradeError> {
         match self {
             Self::Quadlet(file) => file.downgrade(version),
-            Self::Kubernetes(_) => Ok(()),
+            Self::Kubernetes(_) | Self::Generated(_) => Ok(()),
         }
     }
 }
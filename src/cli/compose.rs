@@ -1,7 +1,19 @@
+mod config;
+mod deploy;
+mod extends;
+mod extension;
+mod include;
+mod interpolate;
+mod links;
+mod merge;
+mod secret;
+
 use std::{
     collections::HashMap,
+    env,
+    fmt::Display,
     fs,
-    io::{self, IsTerminal},
+    io::{self, IsTerminal, Read},
     mem,
     path::{Path, PathBuf},
 };
@@ -11,12 +23,26 @@ use color_eyre::{
     eyre::{bail, ensure, eyre, OptionExt, WrapErr},
     Help,
 };
-use compose_spec::{service::Command, Identifier, Network, Networks, Resource, Service, Volumes};
+use compose_spec::{
+    service::{
+        network_config::{NetworkConfig, NetworkMode},
+        ports, Command, Dependency, Restart, VolumesFrom, VolumesFromSource,
+    },
+    Identifier, Name, Network, Networks, Resource, Service, Volumes,
+};
 use indexmap::IndexMap;
 
 use crate::quadlet::{self, container::volume::Source, Globals};
 
-use super::{k8s, Container, File, GlobalArgs, Unit};
+use self::{
+    config::Configs,
+    extension::{Context, Podlet},
+    secret::Secrets,
+};
+
+pub use self::interpolate::{Interpolator, Variables};
+
+use super::{k8s, lenient::LenientReport, Container, File, GeneratedFile, GlobalArgs, Unit};
 
 /// Converts a [`Command`] into a [`Vec<String>`], splitting the [`String`](Command::String) variant
 /// as a shell would.
@@ -38,6 +64,7 @@ pub fn command_try_into_vec(command: Command) -> color_eyre::Result<Vec<String>>
 }
 
 /// [`Args`] for the `podlet compose` subcommand.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Args, Debug, Clone, PartialEq, Eq)]
 pub struct Compose {
     /// Create a `.pod` file and link it with each `.container` file.
@@ -60,16 +87,69 @@ pub struct Compose {
     #[arg(long, conflicts_with = "pod")]
     pub kube: bool,
 
-    /// The compose file to convert
+    #[command(flatten)]
+    pub kube_options: k8s::Options,
+
+    /// Environment file to read variables from for interpolation in the compose file
+    ///
+    /// Can be specified multiple times, variables in later files take precedence.
+    ///
+    /// If not provided, the `.env` file in the same directory as the compose file (or the current
+    /// directory if reading from stdin) is used, if it exists.
+    /// Variables set in Podlet's environment always take precedence.
+    #[arg(long, value_name = "PATH")]
+    pub env_file: Vec<PathBuf>,
+
+    /// Keep variables in the compose file as references for systemd to expand
+    ///
+    /// Instead of substituting variables with their values, they are converted to `${VARIABLE}`.
+    /// Default values are added to the [Service] section of each Quadlet file with `Environment=`
+    /// and the environment files used are added with `EnvironmentFile=`.
+    #[arg(long, conflicts_with = "kube")]
+    pub keep_variables: bool,
+
+    /// Drop unsupported service fields instead of returning an error
+    ///
+    /// Only service fields which are not supported at all, such as `build`, are dropped.
+    /// Unsupported values or options of supported fields, such as an `npipe` volume or a `deploy`
+    /// option, still return an error. A report of the dropped fields, with the reason each is not
+    /// supported, is printed after converting. The files are still created.
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// Name of the compose project
+    ///
+    /// Overrides the top-level `name` field in the compose file.
+    #[arg(long, value_name = "NAME")]
+    pub project_name: Option<Name>,
+
+    /// Name networks, volumes, and containers the way Docker Compose does
+    ///
+    /// Networks and volumes without a `name` are named "{project}_{network/volume}" with
+    /// `NetworkName=` and `VolumeName=`. Containers without a `container_name` are named
+    /// "{project}-{service}-1" with `ContainerName=`. This allows reusing the networks and volumes
+    /// of a stack previously run with Docker Compose.
+    ///
+    /// The project name is taken from --project-name, the top-level `name` field in the compose
+    /// file, or the name of the directory containing the compose file, in that order.
+    #[arg(long, conflicts_with = "kube")]
+    #[allow(clippy::struct_field_names)]
+    pub compose_naming: bool,
+
+    /// The compose file(s) to convert
     ///
     /// If `-` or not provided and stdin is not a terminal,
     /// the compose file will be read from stdin.
     ///
     /// If not provided, and stdin is a terminal, Podlet will look for (in order)
     /// `compose.yaml`, `compose.yml`, `docker-compose.yaml`, and `docker-compose.yml`,
-    /// in the current working directory.
+    /// in the current working directory. If found, `compose.override.yaml` or
+    /// `compose.override.yml` is also used, if it exists.
+    ///
+    /// If multiple files are given, they are merged in order, following the compose spec's merge
+    /// rules. Relative paths are resolved from the directory of the first file.
     #[allow(clippy::struct_field_names)]
-    pub compose_file: Option<PathBuf>,
+    pub compose_file: Vec<PathBuf>,
 }
 
 impl Compose {
@@ -82,6 +162,7 @@ impl Compose {
     /// - Reading/deserializing the compose file.
     /// - Converting the compose file to Kubernetes YAML.
     /// - Converting the compose file to Quadlet files.
+    #[allow(clippy::too_many_lines)]
     pub fn try_into_files(
         self,
         unit: Option<Unit>,
@@ -90,131 +171,631 @@ impl Compose {
         let Self {
             pod,
             kube,
+            kube_options,
+            env_file,
+            keep_variables,
+            lenient,
+            project_name,
+            compose_naming,
             compose_file,
         } = self;
 
-        let compose = read_from_file_or_stdin(compose_file.as_deref())
-            .wrap_err("error reading compose file")?;
+        let (mut compose, interpolator, project_directory) =
+            read_compose(&compose_file, &env_file, keep_variables)?;
+
+        if project_name.is_some() {
+            compose.name = project_name;
+        }
+
+        for (name, service) in &mut compose.services {
+            deploy::merge_resources(service)
+                .wrap_err_with(|| format!("error converting `deploy` of service `{name}`"))?;
+        }
 
-        if kube {
-            let mut k8s_file = k8s::File::try_from(compose)
-                .wrap_err("error converting compose file into Kubernetes YAML")?;
+        let mut report = lenient.then(LenientReport::default);
 
-            let kube =
-                quadlet::Kube::new(PathBuf::from(format!("{}-kube.yaml", k8s_file.name)).into());
-            let quadlet_file = quadlet::File {
-                name: k8s_file.name.clone(),
+        let files = if kube {
+            kube_try_into_files(
+                compose,
+                &project_directory,
+                &interpolator,
+                kube_options,
                 unit,
-                resource: kube.into(),
-                globals: Globals::default(),
-                service: None,
                 install,
-            };
-
-            k8s_file.name.push_str("-kube");
-            Ok(vec![quadlet_file.into(), k8s_file.into()])
+                report.as_mut(),
+            )?
         } else {
             let compose_spec::Compose {
                 version: _,
                 name,
-                include,
-                services,
-                networks,
-                volumes,
+                // Resolved when reading the compose file.
+                include: _,
+                mut services,
+                mut networks,
+                mut volumes,
                 configs,
                 secrets,
-                extensions,
+                mut extensions,
             } = compose;
 
-            let pod_name = pod
+            let service_podlets = Podlet::take_from_services(&mut services)?;
+
+            // Legacy `links` become network aliases and dependencies of the linking service.
+            links::into_aliases(&mut services, &mut networks)?;
+
+            let project_name = compose_naming
+                .then(|| {
+                    name.clone()
+                        .or_else(|| directory_project_name(&project_directory))
+                        .ok_or_eyre("could not determine the project name for `--compose-naming`")
+                        .suggestion("set the project name with `--project-name`")
+                })
+                .transpose()?;
+            if let Some(project_name) = &project_name {
+                add_project_names(&mut networks, project_name);
+                add_project_names(&mut volumes, project_name);
+            }
+
+            let resources = Resources {
+                configs: Configs::resolve(
+                    configs,
+                    &project_directory,
+                    name.as_ref().map(AsRef::as_ref),
+                    &interpolator,
+                )
+                .wrap_err("error resolving `configs`")?,
+                secrets: Secrets::resolve(secrets, &project_directory)
+                    .wrap_err("error resolving `secrets`")?,
+                network_names: resource_names(&networks),
+                external_volumes: external_names(&volumes),
+                podlet: Podlet::take(&mut extensions, Context::TopLevel)?,
+                service_podlets,
+                service_scales: services
+                    .iter()
+                    .filter_map(|(name, service)| {
+                        service
+                            .scale
+                            .filter(|scale| *scale > 1)
+                            .map(|scale| (name.clone(), scale))
+                    })
+                    .collect(),
+                project_name: project_name.map(Into::into),
+            };
+
+            let pod_name: Option<String> = pod
                 .then(|| name.ok_or_eyre("`name` is required when using `--pod`"))
                 .transpose()?
                 .map(Into::into);
 
-            ensure!(include.is_empty(), "`include` is not supported");
-            ensure!(configs.is_empty(), "`configs` is not supported");
-            ensure!(
-                secrets.values().all(Resource::is_external),
-                "only external `secrets` are supported",
-            );
-            ensure!(
-                extensions.is_empty(),
-                "compose extensions are not supported"
+            let mut files = parts_try_into_files(
+                services,
+                networks,
+                volumes,
+                &resources,
+                pod_name.as_deref(),
+                unit.as_ref(),
+                install.as_ref(),
+                report.as_mut(),
+            )
+            .wrap_err("error converting compose file into Quadlet files")?;
+            files.extend(
+                resources
+                    .configs
+                    .into_generated_files()
+                    .map(File::Generated),
             );
 
-            parts_try_into_files(services, networks, volumes, pod_name, unit, install)
-                .wrap_err("error converting compose file into Quadlet files")
+            if keep_variables {
+                add_kept_variables(&interpolator, &mut files)?;
+            }
+
+            files
+        };
+
+        if let Some(report) = report.filter(|report| !report.is_empty()) {
+            warn(report);
         }
+
+        Ok(files)
     }
 }
 
-/// Read and deserialize a [`compose_spec::Compose`] from a file at the given [`Path`], stdin, or a
-/// list of default files.
+/// Attempt to convert a compose file into a Kubernetes YAML file and a Quadlet `.kube` file which
+/// uses it.
 ///
-/// If the path is '-', or stdin is not a terminal, the compose file is deserialized from stdin.
-/// If a path is not provided, the files `compose.yaml`, `compose.yml`, `docker-compose.yaml`,
-/// and `docker-compose.yml` are, in order, looked for in the current directory.
+/// If the compose file has `configs` or a service has an `env_file`, their content is added to
+/// Kubernetes config maps in a separate file. The pods' networks are added to the `.kube` file
+/// with `Network=` and `.network` Quadlet files are created for the compose networks.
 ///
 /// # Errors
 ///
-/// Returns an error if:
+/// Returns an error if a config could not be resolved, `x-podlet` is used for a service, network,
+/// or volume, or the compose file could not be converted into Kubernetes YAML or Quadlet files.
+fn kube_try_into_files(
+    mut compose: compose_spec::Compose,
+    project_directory: &Path,
+    interpolator: &Interpolator,
+    options: k8s::Options,
+    unit: Option<Unit>,
+    install: Option<quadlet::Install>,
+    report: Option<&mut LenientReport>,
+) -> color_eyre::Result<Vec<File>> {
+    config::resolve_content(&mut compose.configs, project_directory, interpolator)
+        .wrap_err("error resolving `configs`")?;
+
+    let podlet = Podlet::take(&mut compose.extensions, Context::TopLevel)?;
+    extension::ensure_top_level_only(&compose)
+        .suggestion("`x-podlet` is only supported at the top level when using `--kube`")?;
+
+    let network_files = networks_try_into_quadlet_files(
+        compose.networks.clone(),
+        unit.as_ref(),
+        install.as_ref(),
+        &podlet,
+    )
+    .map(|result| result.map(Into::into))
+    .collect::<color_eyre::Result<Vec<File>>>()?;
+
+    let mut k8s_file =
+        k8s::File::try_from_compose(compose, project_directory, interpolator, options, report)
+            .wrap_err("error converting compose file into Kubernetes YAML")?;
+
+    let mut kube = quadlet::Kube::new(PathBuf::from(format!("{}-kube.yaml", k8s_file.name)).into());
+    kube.network = mem::take(&mut k8s_file.networks);
+
+    // The config maps are placed in a separate file so they can be referenced by the
+    // `.kube` file with `ConfigMap=`.
+    let config_map = (!k8s_file.config_maps.is_empty())
+        .then(|| {
+            mem::take(&mut k8s_file.config_maps)
+                .iter()
+                .map(serde_yaml::to_string)
+                .collect::<Result<Vec<_>, _>>()
+                .wrap_err("error serializing Kubernetes config maps")
+        })
+        .transpose()?
+        .map(|config_maps| {
+            let name = format!("{}-configmap.yaml", k8s_file.name);
+            kube.config_map.push(name.clone().into());
+            File::Generated(GeneratedFile {
+                name,
+                content: config_maps.join("---\n"),
+            })
+        });
+    // The secret is placed in a separate file so its values are not in the file with the pods.
+    // Unlike config maps, it cannot be referenced by the `.kube` file.
+    let secret = k8s_file
+        .secret
+        .take()
+        .map(|secret| {
+            let name = format!("{}-secret.yaml", k8s_file.name);
+            warn(format_args!(
+                "the values of the compose `secrets` are written to `{name}`, \
+                    create the Podman secret with `podman kube play {name}` \
+                    before starting the `.kube` unit and keep the file private"
+            ));
+            serde_yaml::to_string(&secret)
+                .wrap_err("error serializing Kubernetes secret")
+                .map(|content| File::Generated(GeneratedFile { name, content }))
+        })
+        .transpose()?;
+    // systemd restarts the `.kube` unit according to the pods' restart policy, so all pods must
+    // have the same one.
+    let mut restart_policies = k8s_file
+        .pods
+        .iter()
+        .filter_map(|pod| pod.spec.as_ref()?.restart_policy.as_deref());
+    let restart_policy = restart_policies.next();
+    if let Some(other) = restart_policies.find(|other| Some(*other) != restart_policy) {
+        return Err(eyre!(
+            "pod `restartPolicy` `{other}` conflicts with `{}` of another pod",
+            restart_policy.unwrap_or_default()
+        )
+        .note("the `.kube` file has one `Restart=` option for all of its pods")
+        .suggestion("use the same `restart` policy for each service"));
+    }
+    let service = restart_policy.map(|restart_policy| {
+        super::Service::from(match restart_policy {
+            "Never" => Restart::No,
+            "OnFailure" => Restart::OnFailure,
+            _ => Restart::Always,
+        })
+    });
+    let mut quadlet_file = quadlet::File {
+        name: k8s_file.name.clone(),
+        unit,
+        resource: kube.into(),
+        globals: Globals::default(),
+        service,
+        install,
+    };
+    podlet.apply(&mut quadlet_file);
+
+    k8s_file.name.push_str("-kube");
+    Ok([quadlet_file.into(), k8s_file.into()]
+        .into_iter()
+        .chain(config_map)
+        .chain(secret)
+        .chain(network_files)
+        .collect())
+}
+
+/// Read, interpolate, and merge the `compose_files`, and resolve their `include` entries and
+/// services' `extends` attributes.
 ///
-/// - There was an error opening the given file.
-/// - Stdin was selected and stdin is a terminal.
-/// - No path was given and none of the default files could be opened.
-/// - There was an error deserializing [`compose_spec::Compose`].
-fn read_from_file_or_stdin(path: Option<&Path>) -> color_eyre::Result<compose_spec::Compose> {
-    let (compose_file, path) = if let Some(path) = path {
-        if path.as_os_str() == "-" {
-            return read_from_stdin();
+/// Returns the resulting [`compose_spec::Compose`], the [`Interpolator`] used, which holds the
+/// kept variables if `keep_variables` is `true`, and the project directory relative paths are
+/// resolved from.
+///
+/// # Errors
+///
+/// Returns an error if there was an error reading, interpolating, merging, or deserializing the
+/// compose files, or resolving their includes or extended services.
+fn read_compose(
+    compose_files: &[PathBuf],
+    env_files: &[PathBuf],
+    keep_variables: bool,
+) -> color_eyre::Result<(compose_spec::Compose, Interpolator, PathBuf)> {
+    let compose_files = read_compose_files(compose_files).wrap_err("error reading compose file")?;
+
+    let project_directory = compose_files
+        .first()
+        .and_then(|file| file.path.as_deref())
+        .and_then(Path::parent)
+        .map(Path::to_owned)
+        .unwrap_or_default();
+    let variables = Variables::from_env_files(env_files, &project_directory)?;
+    let mut interpolator = Interpolator::new(variables, keep_variables);
+
+    // Canonical paths of the compose files, used to detect include cycles.
+    let mut include_stack = compose_files
+        .iter()
+        .filter_map(|file| file.path.as_deref())
+        .map(fs::canonicalize)
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err("error reading compose file")?;
+
+    let mut compose = serde_yaml::Value::Null;
+    for ComposeFile { path, mut value } in compose_files {
+        interpolator.interpolate(&mut value).wrap_err_with(|| {
+            path.as_deref().map_or_else(
+                || String::from("error interpolating compose file from stdin"),
+                |path| format!("error interpolating compose file `{}`", path.display()),
+            )
+        })?;
+        if compose.is_null() {
+            compose = value;
+        } else {
+            merge::merge(&mut compose, value);
         }
-        let compose_file = fs::File::open(path)
-            .wrap_err("could not open provided compose file")
-            .suggestion("make sure you have the proper permissions for the given file")?;
-        (compose_file, path)
+    }
+
+    include::resolve(
+        &mut compose,
+        &project_directory,
+        &mut interpolator,
+        &mut include_stack,
+    )
+    .wrap_err("error resolving `include`")?;
+
+    extends::resolve(&mut compose, &project_directory, &mut interpolator)
+        .wrap_err("error resolving `extends`")?;
+
+    merge::strip_tags(&mut compose);
+    extension::strip_unsupported(&mut compose);
+
+    let compose = serde_yaml::from_value(compose).wrap_err("invalid compose file");
+    let compose = if keep_variables {
+        compose.suggestion(
+            "some fields, like `image` and `ports`, are validated and cannot contain \
+                variable references when using `--keep-variables`",
+        )
     } else {
+        compose
+    }?;
+
+    Ok((compose, interpolator, project_directory))
+}
+
+/// Add the variables kept by the `interpolator` to the [Service] section of each Quadlet file.
+///
+/// Variables with default values are added with `Environment=` and the environment files used are
+/// added with `EnvironmentFile=`.
+///
+/// # Errors
+///
+/// Returns an error if the current working directory could not be determined.
+fn add_kept_variables(interpolator: &Interpolator, files: &mut [File]) -> color_eyre::Result<()> {
+    if interpolator.kept().is_empty() {
+        return Ok(());
+    }
+
+    for name in interpolator.kept_without_value() {
+        warn(format_args!(
+            "the `{name}` variable has no default value and is not set in an environment file, \
+                it must be set in the environment of the generated services"
+        ));
+    }
+
+    let current_dir = env::current_dir().wrap_err("error getting current working directory")?;
+    let env_files: Vec<PathBuf> = interpolator
+        .env_files()
+        .iter()
+        .map(|path| super::absolute_clean_path(&current_dir, path))
+        .collect();
+
+    for file in files {
+        if let File::Quadlet(file) = file {
+            let service = file.service.get_or_insert_with(Default::default);
+            for (name, default) in interpolator.kept() {
+                if let Some(default) = default {
+                    service.add_environment(name, default);
+                }
+            }
+            for path in &env_files {
+                service.add_environment_file(path.clone());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a warning `message` to stderr.
+pub(super) fn warn(message: impl Display) {
+    eprintln!("Warning: {message}");
+}
+
+/// Print an informational `message` to stderr.
+fn note(message: impl Display) {
+    eprintln!("Note: {message}");
+}
+
+/// A compose file read as YAML.
+#[derive(Debug)]
+struct ComposeFile {
+    /// Path to the compose file, [`None`] if read from stdin.
+    path: Option<PathBuf>,
+
+    /// The YAML contents of the compose file.
+    value: serde_yaml::Value,
+}
+
+/// Read compose files as YAML from the given `paths`, stdin, or a list of default files.
+///
+/// If a path is '-', or no paths are given and stdin is not a terminal, a compose file is read
+/// from stdin. If no paths are given, the files `compose.yaml`, `compose.yml`,
+/// `docker-compose.yaml`, and `docker-compose.yml` are, in order, looked for in the current
+/// directory. If one is found, `compose.override.yaml` or `compose.override.yml` is also read, if
+/// it exists.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - There was an error opening one of the given files.
+/// - Stdin was selected and stdin is a terminal, or stdin was selected more than once.
+/// - No paths were given and none of the default files could be opened.
+/// - There was an error deserializing YAML.
+fn read_compose_files(paths: &[PathBuf]) -> color_eyre::Result<Vec<ComposeFile>> {
+    if paths.is_empty() {
         const FILE_NAMES: [&str; 4] = [
             "compose.yaml",
             "compose.yml",
             "docker-compose.yaml",
             "docker-compose.yml",
         ];
+        const OVERRIDE_FILE_NAMES: [&str; 2] = ["compose.override.yaml", "compose.override.yml"];
 
         if !io::stdin().is_terminal() {
-            return read_from_stdin();
+            return read_from_stdin().map(|value| vec![value]);
         }
 
-        let mut result = None;
-        for file_name in FILE_NAMES {
-            if let Ok(compose_file) = fs::File::open(file_name) {
-                result = Some((compose_file, file_name.as_ref()));
-                break;
-            }
+        let path = FILE_NAMES
+            .into_iter()
+            .map(Path::new)
+            .find(|path| path.is_file())
+            .ok_or_eyre(
+                "a compose file was not provided and none of \
+                    `compose.yaml`, `compose.yml`, `docker-compose.yaml`, or `docker-compose.yml` \
+                    exist in the current directory",
+            )?;
+        let mut files = vec![read_from_file(path)?];
+
+        if let Some(path) = OVERRIDE_FILE_NAMES
+            .into_iter()
+            .map(Path::new)
+            .find(|path| path.is_file())
+        {
+            files.push(read_from_file(path)?);
         }
 
-        result.ok_or_eyre(
-            "a compose file was not provided and none of \
-                `compose.yaml`, `compose.yml`, `docker-compose.yaml`, or `docker-compose.yml` \
-                exist in the current directory or could not be read",
-        )?
-    };
+        Ok(files)
+    } else {
+        ensure!(
+            paths.iter().filter(|path| path.as_os_str() == "-").count() <= 1,
+            "stdin (`-`) can only be used for one compose file"
+        );
 
-    serde_yaml::from_reader(compose_file)
-        .wrap_err_with(|| format!("File `{}` is not a valid compose file", path.display()))
+        paths
+            .iter()
+            .map(|path| {
+                if path.as_os_str() == "-" {
+                    read_from_stdin()
+                } else {
+                    read_from_file(path)
+                }
+            })
+            .collect()
+    }
 }
 
-/// Read and deserialize [`compose_spec::Compose`] from stdin.
+/// Read a compose file as YAML from the file at `path`.
+///
+/// # Errors
+///
+/// Returns an error if the file could not be opened or there was an error deserializing.
+fn read_from_file(path: &Path) -> color_eyre::Result<ComposeFile> {
+    let compose_file = fs::File::open(path)
+        .wrap_err_with(|| format!("could not open compose file `{}`", path.display()))
+        .suggestion("make sure you have the proper permissions for the given file")?;
+
+    let value = serde_yaml::from_reader(compose_file)
+        .wrap_err_with(|| format!("File `{}` is not valid YAML", path.display()))?;
+
+    Ok(ComposeFile {
+        path: Some(path.to_owned()),
+        value,
+    })
+}
+
+/// Read a compose file as YAML from stdin.
 ///
 /// # Errors
 ///
 /// Returns an error if stdin is a terminal or there was an error deserializing.
-fn read_from_stdin() -> color_eyre::Result<compose_spec::Compose> {
-    let stdin = io::stdin();
+fn read_from_stdin() -> color_eyre::Result<ComposeFile> {
+    let mut stdin = io::stdin();
     if stdin.is_terminal() {
         bail!("cannot read compose from stdin, stdin is a terminal");
     }
 
-    serde_yaml::from_reader(stdin).wrap_err("data from stdin is not a valid compose file")
+    let mut compose = String::new();
+    stdin
+        .read_to_string(&mut compose)
+        .wrap_err("error reading from stdin")?;
+    let value = serde_yaml::from_str(&compose).wrap_err("data from stdin is not valid YAML")?;
+
+    Ok(ComposeFile { path: None, value })
+}
+
+/// Resolved top-level compose resources which are added to the Quadlet files of the services which
+/// use them.
+#[derive(Debug, Default)]
+struct Resources {
+    configs: Configs,
+    secrets: Secrets,
+
+    /// Map of network identifiers to their names, for external networks and networks with a
+    /// `name`.
+    network_names: HashMap<Identifier, String>,
+
+    /// Map of external volume identifiers to their names.
+    external_volumes: HashMap<Identifier, String>,
+
+    /// Top-level `x-podlet` extension, applied to every Quadlet file.
+    podlet: Podlet,
+
+    /// Each service's `x-podlet` extension.
+    service_podlets: HashMap<Identifier, Podlet>,
+
+    /// Number of instances of each scaled service, which are converted into template units.
+    service_scales: HashMap<Identifier, u64>,
+
+    /// Project name used to name containers the way Docker Compose does, set with
+    /// `--compose-naming`.
+    project_name: Option<String>,
+}
+
+/// Create a map of the identifiers of external compose `resources` to their names.
+///
+/// The name of an external resource defaults to its identifier.
+fn external_names<T>(
+    resources: &IndexMap<Identifier, Option<Resource<T>>>,
+) -> HashMap<Identifier, String> {
+    resources
+        .iter()
+        .filter_map(|(id, resource)| match resource {
+            Some(Resource::External { name }) => {
+                Some((id.clone(), name.clone().unwrap_or_else(|| id.to_string())))
+            }
+            Some(Resource::Compose(_)) | None => None,
+        })
+        .collect()
+}
+
+/// Create a map of the identifiers of compose `resources` to their names, for external resources
+/// and resources with a `name`.
+fn resource_names<T: HasName>(
+    resources: &IndexMap<Identifier, Option<Resource<T>>>,
+) -> HashMap<Identifier, String> {
+    let mut names = external_names(resources);
+    names.extend(
+        resources
+            .iter()
+            .filter_map(|(id, resource)| match resource {
+                Some(Resource::Compose(resource)) => {
+                    resource.name().map(|name| (id.clone(), name.to_owned()))
+                }
+                Some(Resource::External { .. }) | None => None,
+            }),
+    );
+    names
+}
+
+/// Compose networks and volumes, which may have a `name`.
+trait HasName: Default {
+    /// The resource's `name`, if set.
+    fn name(&self) -> Option<&str>;
+
+    /// Set the resource's `name`.
+    fn set_name(&mut self, name: String);
+}
+
+impl HasName for Network {
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = Some(name);
+    }
+}
+
+impl HasName for compose_spec::Volume {
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = Some(name);
+    }
+}
+
+/// Name compose `resources` without a `name` "{project}_{id}", the way Docker Compose does.
+fn add_project_names<T: HasName>(
+    resources: &mut IndexMap<Identifier, Option<Resource<T>>>,
+    project_name: &Name,
+) {
+    for (id, resource) in resources {
+        if let Resource::Compose(resource) =
+            resource.get_or_insert_with(|| Resource::Compose(T::default()))
+        {
+            if resource.name().is_none() {
+                resource.set_name(format!("{project_name}_{id}"));
+            }
+        }
+    }
+}
+
+/// Determine the compose project name from the name of the `project_directory`, the way Docker
+/// Compose does.
+///
+/// The name is lowercased and characters other than ASCII letters, digits, `_`, and `-` are
+/// removed.
+fn directory_project_name(project_directory: &Path) -> Option<Name> {
+    let directory = project_directory
+        .canonicalize()
+        .ok()?
+        .file_name()?
+        .to_string_lossy()
+        .to_lowercase();
+    let name: String = directory
+        .chars()
+        .filter(|char| matches!(char, 'a'..='z' | '0'..='9' | '_' | '-'))
+        .skip_while(|char| matches!(char, '_' | '-'))
+        .collect();
+    Name::new(name).ok()
 }
 
 /// Attempt to convert [`Service`]s, [`Networks`], and [`Volumes`] into [`File`]s.
@@ -223,110 +804,289 @@ fn read_from_stdin() -> color_eyre::Result<compose_spec::Compose> {
 ///
 /// Returns an error if a [`Service`], [`Network`], or [`Volume`](compose_spec::Volume) could not be
 /// converted into a [`quadlet::File`].
+#[allow(clippy::too_many_arguments)]
 fn parts_try_into_files(
-    services: IndexMap<Identifier, Service>,
+    mut services: IndexMap<Identifier, Service>,
     networks: Networks,
     volumes: Volumes,
-    pod_name: Option<String>,
-    unit: Option<Unit>,
-    install: Option<quadlet::Install>,
+    resources: &Resources,
+    pod_name: Option<&str>,
+    unit: Option<&Unit>,
+    install: Option<&quadlet::Install>,
+    mut report: Option<&mut LenientReport>,
 ) -> color_eyre::Result<Vec<File>> {
-    // Get a map of volumes to whether the volume has options associated with it for use in
-    // converting a service into a Quadlet file. Extra volume options must be specified in a
-    // separate Quadlet file which is referenced from the container Quadlet file.
-    let volume_has_options = volumes
+    volumes_from_into_containers(&mut services, pod_name, resources)?;
+
+    // Volumes with options are converted first to get a map of volumes to the names of their
+    // Quadlet files for use in converting a service into a Quadlet file. Extra volume options must
+    // be specified in a separate Quadlet file which is referenced from the container Quadlet file.
+    let volumes: Vec<_> = volumes_try_into_quadlet_files(volumes, unit, install, &resources.podlet)
+        .collect::<color_eyre::Result<_>>()?;
+    let volume_files = volumes
         .iter()
-        .map(|(name, volume)| {
-            let has_options = volume
-                .as_ref()
-                .and_then(Resource::as_compose)
-                .is_some_and(|volume| !volume.is_empty());
-            (name.clone(), has_options)
-        })
+        .map(|(id, file)| (id.clone(), file.name.clone()))
+        .collect();
+
+    // Services which share a network namespace with `network_mode: service:` are put in a pod
+    // named after the service whose network namespace they share, unless all services are put in
+    // a pod with `--pod`.
+    let service_pods = if pod_name.is_some() {
+        HashMap::new()
+    } else {
+        network_namespace_pods(&services)?
+    };
+    let mut pods: IndexMap<String, quadlet::Pod> = pod_name
+        .iter()
+        .map(|name| ((*name).to_owned(), quadlet::Pod::default()))
         .collect();
 
-    let mut pod_ports = Vec::new();
     let mut files = services
         .into_iter()
-        .map(|(name, service)| {
+        .map(|(name, mut service)| {
+            if let Some(NetworkConfig::NetworkMode(NetworkMode::Service(_))) =
+                service.network_config
+            {
+                // The network namespace is shared through the pod.
+                service.network_config = None;
+            }
+            let scale = service.scale;
+            let custom_name = resources
+                .service_podlets
+                .get(&name)
+                .and_then(Podlet::name)
+                .is_some();
             let mut file = service_try_into_quadlet_file(
                 service,
-                name,
-                unit.clone(),
-                install.clone(),
-                &volume_has_options,
+                &name,
+                unit.cloned(),
+                install.cloned(),
+                &volume_files,
+                resources,
+                report.as_deref_mut(),
             )?;
             if let (
-                Some(pod_name),
+                Some(pod),
                 quadlet::File {
-                    name,
+                    name: file_name,
                     resource: quadlet::Resource::Container(container),
                     ..
                 },
-            ) = (&pod_name, &mut file)
-            {
-                *name = format!("{pod_name}-{name}");
-                pod_ports.extend(mem::take(&mut container.publish_port));
-                container.pod = Some(format!("{pod_name}.pod"));
+            ) = (
+                pod_name.or_else(|| service_pods.get(&name).map(String::as_str)),
+                &mut file,
+            ) {
+                let quadlet_pod = pods.entry(pod.to_owned()).or_default();
+                if pod_name.is_some() {
+                    if !custom_name {
+                        *file_name = format!("{pod}-{file_name}");
+                    }
+                } else {
+                    // Containers in a pod cannot join networks, the pod must.
+                    for network in mem::take(&mut container.network) {
+                        if !quadlet_pod.network.contains(&network) {
+                            quadlet_pod.network.push(network);
+                        }
+                    }
+                }
+                quadlet_pod
+                    .publish_port
+                    .extend(mem::take(&mut container.publish_port));
+                container.pod = Some(format!("{pod}.pod"));
+            }
+            if let Some(scale) = scale.filter(|scale| *scale > 1) {
+                note_template(&file.name, scale);
             }
             Ok(file)
         })
         .chain(networks_try_into_quadlet_files(
             networks,
-            unit.as_ref(),
-            install.as_ref(),
-        ))
-        .chain(volumes_try_into_quadlet_files(
-            volumes,
-            unit.as_ref(),
-            install.as_ref(),
+            unit,
+            install,
+            &resources.podlet,
         ))
+        .chain(volumes.into_iter().map(|(_, file)| Ok(file)))
         .map(|result| result.map(Into::into))
         .collect::<Result<Vec<File>, _>>()?;
 
-    if let Some(name) = pod_name {
-        let pod = quadlet::Pod {
-            publish_port: pod_ports,
-            ..quadlet::Pod::default()
-        };
-        let pod = quadlet::File {
+    for (name, pod) in pods {
+        let mut pod = quadlet::File {
             name,
-            unit,
+            unit: unit.cloned(),
             resource: pod.into(),
             globals: Globals::default(),
             service: None,
-            install,
+            install: install.cloned(),
         };
+        resources.podlet.apply(&mut pod);
         files.push(pod.into());
     }
 
     Ok(files)
 }
 
+/// Note how to start `scale` instances of the template Quadlet container file `name`.
+fn note_template(name: &str, scale: u64) {
+    note(format_args!(
+        "`{name}.container` is a template, start {scale} instances with \
+            `systemctl --user start {name}{{1..{scale}}}.service` or create symlinks \
+            named `{name}1.container` to `{name}{scale}.container` pointing to it \
+            to start them at boot"
+    ));
+}
+
+/// Resolve the services in the `volumes_from` of compose `services` into the names of their
+/// containers, adding a dependency on each.
+///
+/// A service's container name is its `container_name` or, if not set, "{project}-{service}-1" with
+/// `--compose-naming` or the Quadlet default of `systemd-{file name}`, where the file name is
+/// prefixed with the `pod_name`, if any, unless the service has a custom name in its `x-podlet`
+/// extension.
+///
+/// # Errors
+///
+/// Returns an error if a source service does not exist or is scaled.
+fn volumes_from_into_containers(
+    services: &mut IndexMap<Identifier, Service>,
+    pod_name: Option<&str>,
+    resources: &Resources,
+) -> color_eyre::Result<()> {
+    let container_names: HashMap<Identifier, String> = services
+        .iter()
+        .map(|(name, service)| {
+            let container_name = service.container_name.as_ref().map_or_else(
+                || {
+                    let custom_name = resources.service_podlets.get(name).and_then(Podlet::name);
+                    match (&resources.project_name, pod_name, custom_name) {
+                        (Some(project), _, _) => format!("{project}-{name}-1"),
+                        (None, _, Some(custom_name)) => format!("systemd-{custom_name}"),
+                        (None, Some(pod), None) => format!("systemd-{pod}-{name}"),
+                        (None, None, None) => format!("systemd-{name}"),
+                    }
+                },
+                ToString::to_string,
+            );
+            (name.clone(), container_name)
+        })
+        .collect();
+    let scaled: Vec<Identifier> = services
+        .iter()
+        .filter(|(_, service)| service.scale.is_some_and(|scale| scale > 1))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    for (name, service) in services {
+        let mut dependencies = Vec::new();
+        service.volumes_from = mem::take(&mut service.volumes_from)
+            .into_iter()
+            .map(|VolumesFrom { source, read_only }| {
+                let source = match source {
+                    VolumesFromSource::Service(source) => {
+                        let container_name = container_names.get(&source).ok_or_else(|| {
+                            eyre!(
+                                "service `{name}` mounts volumes from service `{source}`, \
+                                    which does not exist"
+                            )
+                        })?;
+                        ensure!(
+                            !scaled.contains(&source),
+                            "service `{name}` cannot mount volumes from service `{source}` as \
+                                it is scaled"
+                        );
+                        dependencies.push(source);
+                        VolumesFromSource::Container(container_name.parse()?)
+                    }
+                    VolumesFromSource::Container(_) => source,
+                };
+                Ok(VolumesFrom { source, read_only })
+            })
+            .collect::<color_eyre::Result<_>>()?;
+
+        if !dependencies.is_empty() {
+            let mut depends_on = mem::take(&mut service.depends_on).into_long();
+            for dependency in dependencies {
+                depends_on.entry(dependency).or_default();
+            }
+            service.depends_on = depends_on.into();
+        }
+    }
+
+    Ok(())
+}
+
+/// Map the compose `services` which share a network namespace, with
+/// `network_mode: service:{name}`, to the name of a pod for each group of services.
+///
+/// Each pod is named after the service whose network namespace the others share.
+///
+/// # Errors
+///
+/// Returns an error if a service shares the network namespace of a service which does not exist, or
+/// services share each other's network namespace in a cycle.
+fn network_namespace_pods(
+    services: &IndexMap<Identifier, Service>,
+) -> color_eyre::Result<HashMap<Identifier, String>> {
+    let mut pods = HashMap::new();
+
+    for name in services.keys() {
+        let mut root = name;
+        let mut chain = vec![name];
+        while let Some(Service {
+            network_config: Some(NetworkConfig::NetworkMode(NetworkMode::Service(service))),
+            ..
+        }) = services.get(root)
+        {
+            ensure!(
+                services.contains_key(service),
+                "service `{root}` uses the network of service `{service}`, which does not exist"
+            );
+            ensure!(
+                !chain.contains(&service),
+                "services `{name}` and `{service}` share network namespaces in a cycle"
+            );
+            chain.push(service);
+            root = service;
+        }
+
+        if root != name {
+            pods.insert(name.clone(), root.to_string());
+            pods.insert(root.clone(), root.to_string());
+        }
+    }
+
+    Ok(pods)
+}
+
 /// Attempt to convert a compose [`Service`] into a [`quadlet::File`].
 ///
-/// `volume_has_options` should be a map from volume [`Identifier`]s to whether the volume has any
-/// options set. It is used to determine whether to link to a [`quadlet::Volume`] in the created
+/// `volume_files` should be a map from volume [`Identifier`]s to the name of the volume's Quadlet
+/// file, for volumes which have one. It is used to link to a [`quadlet::Volume`] in the created
 /// [`quadlet::Container`].
 ///
+/// The service's `configs` and `secrets` are added from the resolved top-level `resources`, as are
+/// the options from the top-level and the service's `x-podlet` extension.
+///
 /// # Errors
 ///
 /// Returns an error if there was an error [adding](Unit::add_dependency()) a service
-/// [`Dependency`](compose_spec::service::Dependency) to the [`Unit`] or converting the [`Service`]
-/// into a [`quadlet::Container`].
+/// [`Dependency`](compose_spec::service::Dependency) to the [`Unit`], converting the [`Service`]
+/// into a [`quadlet::Container`], or adding the service's `configs`.
 fn service_try_into_quadlet_file(
     mut service: Service,
-    name: Identifier,
+    name: &Identifier,
     mut unit: Option<Unit>,
     install: Option<quadlet::Install>,
-    volume_has_options: &HashMap<Identifier, bool>,
+    volume_files: &HashMap<Identifier, String>,
+    resources: &Resources,
+    report: Option<&mut LenientReport>,
 ) -> color_eyre::Result<quadlet::File> {
+    let podlet = resources.service_podlets.get(name);
+
     // Add any service dependencies to the [Unit] section of the Quadlet file.
     let dependencies = mem::take(&mut service.depends_on).into_long();
     if !dependencies.is_empty() {
         let unit = unit.get_or_insert_with(Unit::default);
         for (ident, dependency) in dependencies {
-            unit.add_dependency(&ident, dependency).wrap_err_with(|| {
+            add_dependency(unit, &ident, dependency, resources).wrap_err_with(|| {
                 format!("error adding dependency on `{ident}` to service `{name}`")
             })?;
         }
@@ -334,39 +1094,158 @@ fn service_try_into_quadlet_file(
 
     let global_args = GlobalArgs::from_compose(&mut service);
 
-    let restart = service.restart;
+    let scale = service.scale.take();
+    match scale {
+        Some(0) => bail!("`scale: 0` is not supported, service `{name}` would never be started"),
+        Some(2..) => {
+            ensure!(
+                ports::into_long_iter(service.ports.clone()).all(|port| port.published.is_none()),
+                eyre!("service `{name}` cannot be scaled as it publishes fixed host ports")
+                    .suggestion("remove the published ports so Podman picks random host ports")
+            );
+        }
+        Some(1) | None => {}
+    }
+
+    let mut quadlet_service = service
+        .restart
+        .map(super::Service::from)
+        .unwrap_or_default();
+    deploy::take_restart_policy(&mut service, &mut quadlet_service, &mut unit)
+        .wrap_err_with(|| format!("error converting `deploy` of service `{name}`"))?;
+    resources
+        .secrets
+        .add_to_service(&service.secrets, &mut quadlet_service);
+    let configs = mem::take(&mut service.configs);
 
-    let mut container = Container::try_from(service)
-        .map(quadlet::Container::from)
-        .wrap_err_with(|| format!("error converting service `{name}` into a Quadlet container"))?;
+    let mut dropped = Vec::new();
+    let mut container =
+        Container::try_from_compose(service, report.is_some().then_some(&mut dropped))
+            .map(quadlet::Container::from)
+            .wrap_err_with(|| {
+                format!("error converting service `{name}` into a Quadlet container")
+            })?;
+    if let Some(report) = report {
+        report.add(name, dropped);
+    }
 
-    // For each named volume, check to see if it has any options set.
-    // If it does, add `.volume` to the source to link this `.container` file to the generated
-    // `.volume` file.
+    // For each named volume, check to see if it has a Quadlet file.
+    // If it does, link this `.container` file to the generated `.volume` file.
+    // External volumes are referenced by their name.
     for volume in &mut container.volume {
         if let Some(Source::NamedVolume(source)) = &mut volume.source {
-            let volume_has_options = volume_has_options
-                .get(source.as_str())
-                .copied()
-                .unwrap_or_default();
-            if volume_has_options {
-                source.push_str(".volume");
+            if let Some(name) = resources.external_volumes.get(source.as_str()) {
+                source.clone_from(name);
+            } else if let Some(file) = volume_files.get(source.as_str()) {
+                *source = format!("{file}.volume");
             }
         }
     }
 
-    Ok(quadlet::File {
-        name: name.into(),
+    // External networks are referenced by their name.
+    for network in &mut container.network {
+        let (id, options) = network
+            .split_once(':')
+            .map_or((network.as_str(), None), |(id, options)| {
+                (id, Some(options))
+            });
+        if let Some(name) = resources.network_names.get(id) {
+            *network = options.map_or_else(|| name.clone(), |options| format!("{name}:{options}"));
+        }
+    }
+
+    resources
+        .configs
+        .add_to_container(configs, &mut container, &mut quadlet_service)
+        .wrap_err_with(|| format!("error adding `configs` to service `{name}`"))?;
+
+    let file_name = podlet.and_then(Podlet::name).unwrap_or(name.as_str());
+
+    // Scaled services become template units, with the instance added to the container name.
+    let scaled = scale.is_some_and(|scale| scale > 1);
+    set_container_name(
+        &mut container,
+        name,
+        scaled,
+        resources.project_name.as_deref(),
+    );
+    let name = if scaled {
+        format!("{file_name}@")
+    } else {
+        file_name.to_owned()
+    };
+
+    let mut file = quadlet::File {
+        name,
         unit,
         resource: container.into(),
         globals: global_args.into(),
-        service: restart.map(Into::into),
+        service: (!quadlet_service.is_empty()).then_some(quadlet_service),
         install,
-    })
+    };
+    resources.podlet.apply(&mut file);
+    if let Some(podlet) = podlet {
+        podlet.apply(&mut file);
+    }
+
+    Ok(file)
+}
+
+/// Add a dependency on the compose service `ident` to the `unit`.
+///
+/// The dependency's Quadlet file may be renamed with `x-podlet`. Scaled services are template
+/// units, so each of their instances is depended on.
+///
+/// # Errors
+///
+/// Returns an error if the dependency is not supported.
+fn add_dependency(
+    unit: &mut Unit,
+    ident: &Identifier,
+    dependency: Dependency,
+    resources: &Resources,
+) -> color_eyre::Result<()> {
+    let dependency_name = resources
+        .service_podlets
+        .get(ident)
+        .and_then(Podlet::name)
+        .unwrap_or(ident.as_str());
+    match resources.service_scales.get(ident) {
+        Some(scale) => (1..=*scale).try_for_each(|index| {
+            unit.add_dependency(format_args!("{dependency_name}@{index}"), dependency)
+        }),
+        None => unit.add_dependency(dependency_name, dependency),
+    }
+}
+
+/// Set the name of the `container` for the compose service `name`.
+///
+/// Containers of `scaled` services have the instance added to their name. With a `project_name`,
+/// from `--compose-naming`, containers are named "{project}-{service}-{index}", the way Docker
+/// Compose does.
+fn set_container_name(
+    container: &mut quadlet::Container,
+    name: &Identifier,
+    scaled: bool,
+    project_name: Option<&str>,
+) {
+    let default_name = project_name.map_or_else(
+        || name.to_string(),
+        |project_name| format!("{project_name}-{name}"),
+    );
+    if scaled {
+        let container_name = container.container_name.as_deref().unwrap_or(&default_name);
+        container.container_name = Some(format!("{container_name}-%i"));
+    } else if project_name.is_some() && container.container_name.is_none() {
+        container.container_name = Some(format!("{default_name}-1"));
+    }
 }
 
 /// Attempt to convert compose [`Networks`] into an [`Iterator`] of [`quadlet::File`]s.
 ///
+/// External networks are filtered out as they already exist. The options from the top-level
+/// `podlet` extension and each network's `x-podlet` extension are added.
+///
 /// # Errors
 ///
 /// The [`Iterator`] returns an [`Err`] if a [`Network`] could not be converted into a
@@ -375,34 +1254,43 @@ fn networks_try_into_quadlet_files<'a>(
     networks: Networks,
     unit: Option<&'a Unit>,
     install: Option<&'a quadlet::Install>,
+    podlet: &'a Podlet,
 ) -> impl Iterator<Item = color_eyre::Result<quadlet::File>> + 'a {
-    networks.into_iter().map(move |(name, network)| {
-        let network = match network {
+    networks.into_iter().filter_map(move |(name, network)| {
+        let mut network = match network {
             Some(Resource::Compose(network)) => network,
             None => Network::default(),
-            Some(Resource::External { .. }) => {
-                bail!("external networks (`{name}`) are not supported");
-            }
+            Some(Resource::External { .. }) => return None,
         };
-        let network = quadlet::Network::try_from(network).wrap_err_with(|| {
-            format!("error converting network `{name}` into a Quadlet network")
-        })?;
-
-        Ok(quadlet::File {
-            name: name.into(),
-            unit: unit.cloned(),
-            resource: network.into(),
-            globals: Globals::default(),
-            service: None,
-            install: install.cloned(),
-        })
+        let result =
+            Podlet::take(&mut network.extensions, Context::Network).and_then(|network_podlet| {
+                let mut file = quadlet::File {
+                    name: network_podlet.name().unwrap_or(name.as_str()).to_owned(),
+                    unit: unit.cloned(),
+                    resource: quadlet::Network::try_from(network)?.into(),
+                    globals: Globals::default(),
+                    service: None,
+                    install: install.cloned(),
+                };
+                podlet.apply(&mut file);
+                network_podlet.apply(&mut file);
+                Ok(file)
+            });
+        Some(
+            result.wrap_err_with(|| {
+                format!("error converting network `{name}` into a Quadlet network")
+            }),
+        )
     })
 }
 
-/// Attempt to convert compose [`Volumes`] into an [`Iterator`] of [`quadlet::File`]s.
+/// Attempt to convert compose [`Volumes`] into an [`Iterator`] of [`quadlet::File`]s, paired with
+/// the volume's [`Identifier`].
 ///
 /// [`Volume`](compose_spec::Volume)s which are [empty](compose_spec::Volume::is_empty()) are
-/// filtered out as they do not need a `.volume` Quadlet file to define extra options.
+/// filtered out as they do not need a `.volume` Quadlet file to define extra options. External
+/// volumes are also filtered out as they already exist. The options from the top-level `podlet`
+/// extension and each volume's `x-podlet` extension are added.
 ///
 /// # Errors
 ///
@@ -412,26 +1300,222 @@ fn volumes_try_into_quadlet_files<'a>(
     volumes: Volumes,
     unit: Option<&'a Unit>,
     install: Option<&'a quadlet::Install>,
-) -> impl Iterator<Item = color_eyre::Result<quadlet::File>> + 'a {
+    podlet: &'a Podlet,
+) -> impl Iterator<Item = color_eyre::Result<(Identifier, quadlet::File)>> + 'a {
     volumes.into_iter().filter_map(move |(name, volume)| {
-        volume.and_then(|volume| match volume {
-            Resource::Compose(volume) => (!volume.is_empty()).then(|| {
-                quadlet::Volume::try_from(volume)
-                    .wrap_err_with(|| {
-                        format!("error converting volume `{name}` into a Quadlet volume")
-                    })
-                    .map(|volume| quadlet::File {
-                        name: name.into(),
-                        unit: unit.cloned(),
-                        resource: volume.into(),
-                        globals: Globals::default(),
-                        service: None,
-                        install: install.cloned(),
-                    })
-            }),
-            Resource::External { .. } => {
-                Some(Err(eyre!("external volumes (`{name}`) are not supported")))
-            }
-        })
+        let mut volume = match volume? {
+            Resource::Compose(volume) if !volume.is_empty() => volume,
+            Resource::Compose(_) | Resource::External { .. } => return None,
+        };
+        let result =
+            Podlet::take(&mut volume.extensions, Context::Volume).and_then(|volume_podlet| {
+                let mut file = quadlet::File {
+                    name: volume_podlet.name().unwrap_or(name.as_str()).to_owned(),
+                    unit: unit.cloned(),
+                    resource: quadlet::Volume::try_from(volume)?.into(),
+                    globals: Globals::default(),
+                    service: None,
+                    install: install.cloned(),
+                };
+                podlet.apply(&mut file);
+                volume_podlet.apply(&mut file);
+                Ok(file)
+            });
+        Some(
+            result
+                .wrap_err_with(|| format!("error converting volume `{name}` into a Quadlet volume"))
+                .map(|file| (name, file)),
+        )
     })
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_namespace_pods() {
+        let services: IndexMap<Identifier, Service> = serde_yaml::from_str(
+            "vpn:\n  \
+                image: vpn\n\
+            app:\n  \
+                image: app\n  \
+                network_mode: service:vpn\n\
+            sidecar:\n  \
+                image: sidecar\n  \
+                network_mode: service:app\n\
+            solo:\n  \
+                image: solo\n",
+        )
+        .unwrap();
+        let pods = super::network_namespace_pods(&services).unwrap();
+
+        let mut pods: Vec<_> = pods
+            .iter()
+            .map(|(service, pod)| (service.as_str(), pod.as_str()))
+            .collect();
+        pods.sort_unstable();
+        assert_eq!(pods, [("app", "vpn"), ("sidecar", "vpn"), ("vpn", "vpn")]);
+    }
+
+    #[test]
+    fn network_namespace_pods_cycle() {
+        let services: IndexMap<Identifier, Service> = serde_yaml::from_str(
+            "one:\n  \
+                image: one\n  \
+                network_mode: service:two\n\
+            two:\n  \
+                image: two\n  \
+                network_mode: service:one\n",
+        )
+        .unwrap();
+        assert!(super::network_namespace_pods(&services).is_err());
+    }
+
+    #[test]
+    fn volumes_from_into_containers() {
+        let mut services: IndexMap<Identifier, Service> = serde_yaml::from_str(
+            "app:\n  \
+                image: app\n  \
+                volumes_from: [data, 'named:ro', 'container:other']\n\
+            data:\n  \
+                image: data\n\
+            named:\n  \
+                image: named\n  \
+                container_name: store\n",
+        )
+        .unwrap();
+        super::volumes_from_into_containers(&mut services, Some("pod"), &Resources::default())
+            .unwrap();
+
+        let app = services.get("app").unwrap();
+        let volumes_from: Vec<_> = app
+            .volumes_from
+            .iter()
+            .map(|VolumesFrom { source, read_only }| (source.to_string(), *read_only))
+            .collect();
+        assert_eq!(
+            volumes_from,
+            [
+                ("container:systemd-pod-data".to_owned(), false),
+                ("container:store".to_owned(), true),
+                ("container:other".to_owned(), false),
+            ]
+        );
+        let dependencies: Vec<_> = app
+            .depends_on
+            .clone()
+            .into_long()
+            .into_keys()
+            .map(String::from)
+            .collect();
+        assert_eq!(dependencies, ["data", "named"]);
+    }
+
+    #[test]
+    fn scaled_dependency() {
+        let service: Service = serde_yaml::from_str(
+            "image: web\n\
+            depends_on: [worker]\n",
+        )
+        .unwrap();
+        let resources = Resources {
+            service_scales: [(Identifier::new("worker").unwrap(), 2)].into(),
+            ..Resources::default()
+        };
+        let file = service_try_into_quadlet_file(
+            service,
+            &Identifier::new("web").unwrap(),
+            None,
+            None,
+            &HashMap::new(),
+            &resources,
+            None,
+        )
+        .unwrap();
+
+        let file = file.to_string();
+        assert!(file.starts_with(
+            "[Unit]\n\
+            Requires=worker@1.service worker@2.service\n\
+            After=worker@1.service worker@2.service\n"
+        ));
+    }
+
+    #[test]
+    fn kube_restart_conflict() {
+        let compose = |restart| -> compose_spec::Compose {
+            serde_yaml::from_str(&format!(
+                "name: stack\n\
+                services:\n  \
+                    web:\n    \
+                        image: web\n    \
+                        restart: always\n  \
+                    job:\n    \
+                        image: job\n    \
+                        restart: {restart}\n"
+            ))
+            .unwrap()
+        };
+        let interpolator = Interpolator::new(Variables::default(), false);
+        let options = k8s::Options {
+            layout: k8s::Layout::PerService,
+            ..k8s::Options::default()
+        };
+
+        let files = kube_try_into_files(
+            compose("unless-stopped"),
+            Path::new(""),
+            &interpolator,
+            options,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let kube = files.first().unwrap().to_string();
+        assert!(kube.contains("[Service]\nRestart=always\n"));
+
+        assert!(kube_try_into_files(
+            compose("on-failure"),
+            Path::new(""),
+            &interpolator,
+            options,
+            None,
+            None,
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn add_project_names() {
+        let mut volumes: Volumes = serde_yaml::from_str(
+            "data:\n\
+            cache:\n  \
+                name: shared-cache\n\
+            backup:\n  \
+                external: true\n",
+        )
+        .unwrap();
+        super::add_project_names(&mut volumes, &Name::new("stack").unwrap());
+
+        let names: Vec<_> = volumes
+            .values()
+            .map(|volume| match volume {
+                Some(Resource::Compose(volume)) => volume.name.as_deref(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, [Some("stack_data"), Some("shared-cache"), None]);
+    }
+
+    #[test]
+    fn directory_project_name() {
+        let directory = env::temp_dir().join("My.Project_1");
+        fs::create_dir_all(&directory).unwrap();
+        let name = super::directory_project_name(&directory).unwrap();
+        assert_eq!(name.as_ref(), "myproject_1");
+    }
+}
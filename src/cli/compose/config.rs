@@ -0,0 +1,355 @@
+//! Support for compose [`configs`](https://github.com/compose-spec/compose-spec/blob/master/08-configs.md).
+//!
+//! File configs are bind mounted read-only into containers. Configs with `content` or
+//! `environment` sources are written to a generated file next to the Quadlet files. If a service
+//! sets the `uid`, `gid`, or `mode` of a config, a Podman secret is created from the config's file
+//! instead, as bind mounts cannot change the ownership or permissions of a file.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    eyre::{bail, ensure, eyre, OptionExt, WrapErr},
+    Help,
+};
+use compose_spec::{config::Source, service::ConfigOrSecret, Identifier, Resource, ShortOrLong};
+use indexmap::IndexMap;
+
+use crate::{
+    cli::{GeneratedFile, Service},
+    escape::command_join,
+    quadlet::{self, container::volume},
+};
+
+use super::interpolate::Interpolator;
+
+/// Resolved compose [`Configs`](compose_spec::Configs) for adding to Quadlet containers.
+#[derive(Debug, Default)]
+pub struct Configs {
+    /// Top-level configs by their identifier.
+    resolved: IndexMap<Identifier, Config>,
+
+    /// Absolute directory relative config file paths are resolved from.
+    project_directory: PathBuf,
+
+    /// Prefix for the names of Podman secrets and generated files created from configs.
+    prefix: String,
+}
+
+/// Source of a compose config's contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Config {
+    /// Path to a file, relative to the project directory.
+    File(PathBuf),
+
+    /// Content written to a generated file.
+    Content(String),
+}
+
+impl Configs {
+    /// Resolve top-level compose `configs`.
+    ///
+    /// `environment` configs take their content from the `interpolator`'s variables.
+    /// `project_name`, if set, is used to prefix the names of any created Podman secrets and
+    /// generated files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a config is external, has extensions, or an `environment` config's
+    /// variable is not set.
+    pub fn resolve(
+        configs: compose_spec::Configs,
+        project_directory: &Path,
+        project_name: Option<&str>,
+        interpolator: &Interpolator,
+    ) -> color_eyre::Result<Self> {
+        let resolved = configs
+            .into_iter()
+            .map(|(id, config)| {
+                resolve_source(config, interpolator)
+                    .wrap_err_with(|| format!("error resolving config `{id}`"))
+                    .map(|config| (id, config))
+            })
+            .collect::<color_eyre::Result<_>>()?;
+
+        let project_directory = env::current_dir()
+            .wrap_err("error getting current working directory")?
+            .join(project_directory);
+
+        Ok(Self {
+            resolved,
+            project_directory,
+            prefix: project_name.map_or_else(String::new, |name| format!("{name}-")),
+        })
+    }
+
+    /// Add the service's `configs` to the Quadlet `container`.
+    ///
+    /// Configs are mounted read-only at their target, `/<source>` by default. If the `uid`, `gid`,
+    /// or `mode` is set, the config is mounted as a Podman secret, which is created by a command
+    /// added to the [Service] section of the Quadlet file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a config is not defined in the top-level `configs`, or the `uid`, `gid`,
+    /// or `mode` is set for a config which is not from a file.
+    pub fn add_to_container(
+        &self,
+        configs: Vec<ShortOrLong<Identifier, ConfigOrSecret>>,
+        container: &mut quadlet::Container,
+        service: &mut Service,
+    ) -> color_eyre::Result<()> {
+        for config in configs {
+            let ConfigOrSecret {
+                source,
+                target,
+                uid,
+                gid,
+                mode,
+                extensions,
+            } = config.into_long();
+
+            ensure!(
+                extensions.is_empty(),
+                "compose extensions are not supported"
+            );
+
+            let config = self.resolved.get(&source).ok_or_else(|| {
+                eyre!("config `{source}` is not defined in the top-level `configs`")
+            })?;
+            let target = target.unwrap_or_else(|| Path::new("/").join(source.as_str()));
+
+            if uid.is_none() && gid.is_none() && mode.is_none() {
+                let path = match config {
+                    Config::File(path) => path.clone(),
+                    Config::Content(_) => Path::new(".").join(file_name(&self.prefix, &source)),
+                };
+                container.volume.push(quadlet::container::Volume {
+                    source: Some(volume::Source::HostPath(path)),
+                    container_path: target,
+                    options: volume::Options {
+                        read_only: true,
+                        ..volume::Options::default()
+                    },
+                });
+                continue;
+            }
+
+            let Config::File(path) = config else {
+                bail!(eyre!(
+                    "`uid`, `gid`, and `mode` are only supported for file configs, \
+                        config `{source}` is not from a file"
+                )
+                .suggestion("set the ownership and permissions of the file in the container"));
+            };
+
+            let secret = format!("{}config-{source}", self.prefix);
+            let path = crate::cli::absolute_clean_path(&self.project_directory, path);
+            service.add_exec_start_pre(command_join([
+                "podman",
+                "secret",
+                "create",
+                "--replace",
+                &secret,
+                &path.to_string_lossy(),
+            ]));
+
+            container.secret.push(
+                [
+                    secret,
+                    String::from("type=mount"),
+                    format!("target={}", target.display()),
+                ]
+                .into_iter()
+                .chain(uid.map(|uid| format!("uid={uid}")))
+                .chain(gid.map(|gid| format!("gid={gid}")))
+                .chain(mode.map(|mode| format!("mode={mode:o}")))
+                .collect::<Vec<_>>()
+                .join(","),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Files for each config with content, which are placed next to the Quadlet files and bind
+    /// mounted into containers.
+    ///
+    /// A trailing newline is added to the content if it does not have one.
+    pub fn into_generated_files(self) -> impl Iterator<Item = GeneratedFile> {
+        let Self {
+            resolved, prefix, ..
+        } = self;
+        resolved
+            .into_iter()
+            .filter_map(move |(id, config)| match config {
+                Config::Content(mut content) => {
+                    if !content.ends_with('\n') {
+                        content.push('\n');
+                    }
+                    Some(GeneratedFile {
+                        name: file_name(&prefix, &id),
+                        content,
+                    })
+                }
+                Config::File(_) => None,
+            })
+    }
+}
+
+/// Name of the generated file for the config `id`, "{project}-config-{id}".
+fn file_name(prefix: &str, id: &Identifier) -> String {
+    format!("{prefix}config-{id}")
+}
+
+/// Resolve the source of each of the top-level compose `configs` to [`Source::Content`].
+///
+/// File configs are read relative to the `project_directory` and `environment` configs take their
+/// content from the `interpolator`'s variables.
+///
+/// # Errors
+///
+/// Returns an error if a config is external, has extensions, a file could not be read, or an
+/// `environment` config's variable is not set.
+pub fn resolve_content(
+    configs: &mut compose_spec::Configs,
+    project_directory: &Path,
+    interpolator: &Interpolator,
+) -> color_eyre::Result<()> {
+    for (id, config) in configs {
+        let content = match resolve_source(config.clone(), interpolator)
+            .wrap_err_with(|| format!("error resolving config `{id}`"))?
+        {
+            Config::File(path) => {
+                let path = project_directory.join(path);
+                fs::read_to_string(&path).wrap_err_with(|| {
+                    format!("error reading file `{}` for config `{id}`", path.display())
+                })?
+            }
+            Config::Content(content) => content,
+        };
+        *config = Resource::Compose(Source::Content(content).into());
+    }
+
+    Ok(())
+}
+
+/// Resolve the [`Source`] of a top-level compose `config` into a [`Config`].
+///
+/// Relative file paths are prefixed with `./` so they are treated as paths by Quadlet.
+/// [`Source::Environment`] is resolved to [`Config::Content`] using the `interpolator`'s
+/// variables.
+///
+/// # Errors
+///
+/// Returns an error if the config is external, has extensions, or an `environment` config's
+/// variable is not set.
+fn resolve_source(
+    config: Resource<compose_spec::Config>,
+    interpolator: &Interpolator,
+) -> color_eyre::Result<Config> {
+    let compose_spec::Config {
+        source,
+        labels: _,
+        extensions,
+    } = match config {
+        Resource::Compose(config) => config,
+        Resource::External { .. } => bail!("external configs are not supported"),
+    };
+
+    ensure!(
+        extensions.is_empty(),
+        "compose extensions are not supported"
+    );
+
+    Ok(match source {
+        Source::File(path) if path.is_relative() && !path.starts_with(".") => {
+            Config::File(Path::new(".").join(path))
+        }
+        Source::File(path) => Config::File(path),
+        Source::Environment(variable) => Config::Content(
+            interpolator
+                .variable(&variable)
+                .ok_or_eyre(format!("environment variable `{variable}` is not set"))?
+                .into_owned(),
+        ),
+        Source::Content(content) => Config::Content(content),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{super::interpolate::Variables, *};
+
+    fn configs() -> Configs {
+        let configs: compose_spec::Configs = serde_yaml::from_str(
+            "file:\n  \
+                file: config.txt\n\
+            content:\n  \
+                content: hello\n",
+        )
+        .unwrap();
+        let interpolator = Interpolator::new(Variables::default(), false);
+        Configs::resolve(configs, Path::new("/project"), Some("name"), &interpolator).unwrap()
+    }
+
+    #[test]
+    fn mount() {
+        let configs = configs();
+        let service_configs =
+            serde_yaml::from_str("[file, {source: content, target: /c}]").unwrap();
+
+        let mut container = quadlet::Container::default();
+        let mut service = Service::default();
+        configs
+            .add_to_container(service_configs, &mut container, &mut service)
+            .unwrap();
+
+        let volumes: Vec<String> = container.volume.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            volumes,
+            ["./config.txt:/file:ro", "./name-config-content:/c:ro"]
+        );
+        assert!(service.is_empty());
+
+        let files: Vec<_> = configs
+            .into_generated_files()
+            .map(|file| (file.name, file.content))
+            .collect();
+        assert_eq!(
+            files,
+            [(String::from("name-config-content"), String::from("hello\n"))]
+        );
+    }
+
+    #[test]
+    fn secret() {
+        let configs = configs();
+        let service_configs =
+            serde_yaml::from_str("[{source: file, uid: '1', mode: 0o440}]").unwrap();
+
+        let mut container = quadlet::Container::default();
+        let mut service = Service::default();
+        configs
+            .add_to_container(service_configs, &mut container, &mut service)
+            .unwrap();
+
+        assert_eq!(
+            container.secret,
+            ["name-config-file,type=mount,target=/file,uid=1,mode=440"]
+        );
+        assert_eq!(
+            service.to_string(),
+            "[Service]\n\
+            ExecStartPre=podman secret create --replace name-config-file /project/config.txt\n"
+        );
+
+        let service_configs = serde_yaml::from_str("[{source: content, mode: 0o440}]").unwrap();
+        assert!(configs
+            .add_to_container(service_configs, &mut container, &mut service)
+            .is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Conversion of the compose service
+//! [`deploy`](https://github.com/compose-spec/compose-spec/blob/master/deploy.md) attribute.
+//!
+//! Only `resources` and `restart_policy` are supported, the other options are specific to
+//! orchestrators like Docker Swarm.
+
+use std::{fmt::Debug, time::Duration};
+
+use color_eyre::eyre::{ensure, WrapErr};
+use compose_spec::service::{
+    deploy::{
+        resources::{Cpus, Limits, Reservations},
+        Mode, Resources, RestartPolicy,
+    },
+    Deploy,
+};
+
+use crate::cli::{Service, Unit};
+
+/// Merge `deploy.resources` of the compose `service` into the service's `cpus`, `mem_limit`,
+/// `pids_limit`, `mem_reservation`, and `cpu_shares` fields, and `deploy.replicas` into `scale`.
+///
+/// A CPU reservation is converted into CPU shares, which give the container a proportional share of
+/// the CPU time when the CPUs are under load.
+///
+/// Afterwards, `deploy` only contains the `restart_policy`, if it was set.
+///
+/// # Errors
+///
+/// Returns an error if an unsupported `deploy` option is set or a resource is set in both `deploy`
+/// and the service with different values.
+pub fn merge_resources(service: &mut compose_spec::Service) -> color_eyre::Result<()> {
+    let Some(Deploy {
+        endpoint_mode,
+        labels,
+        mode,
+        placement,
+        replicas,
+        resources,
+        restart_policy,
+        rollback_config,
+        update_config,
+        extensions,
+    }) = service.deploy.take()
+    else {
+        return Ok(());
+    };
+
+    let unsupported_options = [
+        ("endpoint_mode", endpoint_mode.is_none()),
+        ("labels", labels.is_empty()),
+        ("mode: global", mode != Some(Mode::Global)),
+        ("placement", placement.is_none()),
+        ("rollback_config", rollback_config.is_none()),
+        ("update_config", update_config.is_none()),
+    ];
+    for (option, not_present) in unsupported_options {
+        ensure!(not_present, "`deploy.{option}` is not supported");
+    }
+    ensure!(
+        extensions.is_empty(),
+        "compose extensions are not supported"
+    );
+
+    merge("scale", &mut service.scale, replicas)?;
+
+    if let Some(Resources {
+        limits,
+        reservations,
+        extensions,
+    }) = resources
+    {
+        ensure!(
+            extensions.is_empty(),
+            "compose extensions are not supported"
+        );
+
+        if let Some(Limits {
+            cpus,
+            memory,
+            pids,
+            extensions,
+        }) = limits
+        {
+            ensure!(
+                extensions.is_empty(),
+                "compose extensions are not supported"
+            );
+            merge("cpus", &mut service.cpus, cpus)?;
+            merge("mem_limit", &mut service.mem_limit, memory)?;
+            merge("pids_limit", &mut service.pids_limit, pids)?;
+        }
+
+        if let Some(Reservations {
+            cpus,
+            memory,
+            devices,
+            generic_resources,
+            extensions,
+        }) = reservations
+        {
+            ensure!(
+                devices.is_empty(),
+                "`deploy.resources.reservations.devices` is not supported"
+            );
+            ensure!(
+                generic_resources.is_empty(),
+                "`deploy.resources.reservations.generic_resources` is not supported"
+            );
+            ensure!(
+                extensions.is_empty(),
+                "compose extensions are not supported"
+            );
+            merge("mem_reservation", &mut service.mem_reservation, memory)?;
+            merge(
+                "cpu_shares",
+                &mut service.cpu_shares,
+                cpus.map(cpus_into_shares),
+            )?;
+        }
+    }
+
+    service.deploy = restart_policy.map(|restart_policy| Deploy {
+        restart_policy: Some(restart_policy),
+        ..Deploy::default()
+    });
+
+    Ok(())
+}
+
+/// Convert a number of CPUs into CPU shares, where one CPU is 1024 shares, the same as Kubernetes
+/// does for CPU requests.
+///
+/// The minimum is 2 shares, the lowest value the kernel allows.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn cpus_into_shares(cpus: Cpus) -> u64 {
+    // `Cpus` is always positive and finite.
+    ((cpus.into_inner() * 1024.0).round() as u64).max(2)
+}
+
+/// Merge a `deploy` `value` into the service's `field`.
+///
+/// # Errors
+///
+/// Returns an error if `field` is already set to a different value.
+fn merge<T: PartialEq + Debug>(
+    name: &str,
+    field: &mut Option<T>,
+    value: Option<T>,
+) -> color_eyre::Result<()> {
+    if let Some(value) = value {
+        if let Some(field) = field {
+            ensure!(
+                *field == value,
+                "`{name}` conflicts with its value from `deploy`, {field:?} != {value:?}"
+            );
+        } else {
+            *field = Some(value);
+        }
+    }
+    Ok(())
+}
+
+/// Add a `deploy.restart_policy` to the [Service] and [Unit] sections of a Quadlet file.
+///
+/// The `condition` is converted to `Restart=` and the `delay` to `RestartSec=`. If `max_attempts`
+/// is set, it is converted to `StartLimitBurst=`, with the `window` converted to
+/// `StartLimitIntervalSec=`. If the `window` is not set, the limit is applied indefinitely.
+///
+/// # Errors
+///
+/// Returns an error if the restart policy has extensions.
+fn add_restart_policy(
+    RestartPolicy {
+        condition,
+        delay,
+        max_attempts,
+        window,
+        extensions,
+    }: RestartPolicy,
+    service: &mut Service,
+    unit: &mut Unit,
+) -> color_eyre::Result<()> {
+    ensure!(
+        extensions.is_empty(),
+        "compose extensions are not supported"
+    );
+
+    service.set_restart_condition(condition.unwrap_or_default());
+    if let Some(delay) = delay {
+        service.set_restart_sec(format_duration(delay));
+    }
+
+    if let Some(max_attempts) = max_attempts {
+        let interval = window.map_or_else(|| String::from("infinity"), format_duration);
+        unit.set_start_limit(max_attempts, interval);
+    }
+
+    Ok(())
+}
+
+/// Format a [`Duration`] as a systemd time span, in seconds or milliseconds.
+fn format_duration(duration: Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        duration.as_secs().to_string()
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+/// Convert the compose service's `deploy.restart_policy` and add it to the Quadlet [Service] and
+/// [Unit] sections.
+///
+/// # Errors
+///
+/// Returns an error if the service also sets `restart` or the restart policy has extensions.
+pub fn take_restart_policy(
+    service: &mut compose_spec::Service,
+    quadlet_service: &mut Service,
+    unit: &mut Option<Unit>,
+) -> color_eyre::Result<()> {
+    let Some(restart_policy) = service
+        .deploy
+        .take()
+        .and_then(|deploy| deploy.restart_policy)
+    else {
+        return Ok(());
+    };
+
+    ensure!(
+        service.restart.is_none(),
+        "`restart` and `deploy.restart_policy` cannot both be set"
+    );
+
+    add_restart_policy(
+        restart_policy,
+        quadlet_service,
+        unit.get_or_insert_with(Unit::default),
+    )
+    .wrap_err("error converting `deploy.restart_policy`")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resources() {
+        let mut service: compose_spec::Service = serde_yaml::from_str(
+            "deploy:\n  \
+                resources:\n    \
+                    limits:\n      \
+                        cpus: 0.5\n      \
+                        memory: 1g\n      \
+                        pids: 100\n    \
+                    reservations:\n      \
+                        memory: 512m\n  \
+                restart_policy:\n    \
+                    condition: on-failure\n",
+        )
+        .unwrap();
+        merge_resources(&mut service).unwrap();
+
+        let expected: compose_spec::Service = serde_yaml::from_str(
+            "cpus: 0.5\n\
+            mem_limit: 1g\n\
+            pids_limit: 100\n\
+            mem_reservation: 512m\n\
+            deploy:\n  \
+                restart_policy:\n    \
+                    condition: on-failure\n",
+        )
+        .unwrap();
+        assert_eq!(service, expected);
+    }
+
+    #[test]
+    fn cpu_reservation() {
+        let mut service: compose_spec::Service =
+            serde_yaml::from_str("deploy: {resources: {reservations: {cpus: '0.5'}}}").unwrap();
+        merge_resources(&mut service).unwrap();
+        assert_eq!(service.cpu_shares, Some(512));
+        assert!(service.deploy.is_none());
+
+        let mut service: compose_spec::Service =
+            serde_yaml::from_str("deploy: {resources: {reservations: {cpus: 0.001}}}").unwrap();
+        merge_resources(&mut service).unwrap();
+        assert_eq!(service.cpu_shares, Some(2));
+
+        let mut service: compose_spec::Service = serde_yaml::from_str(
+            "cpu_shares: 100
+deploy: {resources: {reservations: {cpus: 1.0}}}",
+        )
+        .unwrap();
+        assert!(merge_resources(&mut service).is_err());
+    }
+
+    #[test]
+    fn resources_conflict() {
+        let mut service: compose_spec::Service =
+            serde_yaml::from_str("mem_limit: 1g\ndeploy: {resources: {limits: {memory: 2g}}}")
+                .unwrap();
+        assert!(merge_resources(&mut service).is_err());
+    }
+
+    #[test]
+    fn replicas() {
+        let mut service: compose_spec::Service =
+            serde_yaml::from_str("deploy: {replicas: 3}").unwrap();
+        merge_resources(&mut service).unwrap();
+        assert_eq!(service.scale, Some(3));
+        assert!(service.deploy.is_none());
+
+        let mut service: compose_spec::Service =
+            serde_yaml::from_str("scale: 2\ndeploy: {replicas: 3}").unwrap();
+        assert!(merge_resources(&mut service).is_err());
+    }
+
+    #[test]
+    fn swarm_only() {
+        let mut service: compose_spec::Service =
+            serde_yaml::from_str("deploy: {placement: {constraints: [node.role==manager]}}")
+                .unwrap();
+        assert!(merge_resources(&mut service).is_err());
+    }
+
+    #[test]
+    fn restart_policy() {
+        let mut service: compose_spec::Service = serde_yaml::from_str(
+            "deploy:\n  \
+                restart_policy:\n    \
+                    condition: on-failure\n    \
+                    delay: 5s\n    \
+                    max_attempts: 3\n    \
+                    window: 2m\n",
+        )
+        .unwrap();
+
+        let mut quadlet_service = Service::default();
+        let mut unit = None;
+        take_restart_policy(&mut service, &mut quadlet_service, &mut unit).unwrap();
+
+        assert_eq!(
+            quadlet_service.to_string(),
+            "[Service]\nRestart=on-failure\nRestartSec=5\n"
+        );
+        assert_eq!(
+            unit.unwrap().to_string(),
+            "[Unit]\nStartLimitIntervalSec=120\nStartLimitBurst=3\n"
+        );
+    }
+}
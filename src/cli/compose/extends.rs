@@ -0,0 +1,252 @@
+//! Resolution of the compose service
+//! [`extends`](https://github.com/compose-spec/compose-spec/blob/master/05-services.md#extends)
+//! attribute.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, ensure, eyre, OptionExt, WrapErr};
+use serde_yaml::{Mapping, Value};
+
+use super::{
+    include::rebase_service_paths, interpolate::Interpolator, merge, read_from_file, ComposeFile,
+};
+
+/// Resolve and remove the `extends` attribute of each service in the compose file `value`.
+///
+/// Each service is merged onto the service it extends, following the compose spec's merge rules.
+/// Services extended from other files have their relative paths rebased.
+///
+/// `project_directory` is the directory relative paths in `value` are resolved from.
+///
+/// # Errors
+///
+/// Returns an error if an `extends` attribute is invalid, an extended service or file could not
+/// be found, or a cycle is detected.
+pub fn resolve(
+    value: &mut Value,
+    project_directory: &Path,
+    interpolator: &mut Interpolator,
+) -> color_eyre::Result<()> {
+    let Some(services) = value.get_mut("services").and_then(Value::as_mapping_mut) else {
+        return Ok(());
+    };
+
+    let names: Vec<String> = services
+        .keys()
+        .filter_map(Value::as_str)
+        .map(ToOwned::to_owned)
+        .collect();
+
+    let mut resolver = Resolver {
+        interpolator,
+        stack: Vec::new(),
+    };
+    for name in names {
+        resolver
+            .resolve_service(services, &name, Path::new(""), project_directory)
+            .wrap_err_with(|| format!("error resolving `extends` of service `{name}`"))?;
+    }
+
+    Ok(())
+}
+
+/// Resolves `extends` of services, tracking the services being resolved to detect cycles.
+struct Resolver<'a> {
+    interpolator: &'a mut Interpolator,
+
+    /// The file and name of each service currently being resolved.
+    stack: Vec<(PathBuf, String)>,
+}
+
+impl Resolver<'_> {
+    /// Resolve the `extends` attribute of the service `name` within `services`.
+    ///
+    /// `file` is the canonical path of the compose file `services` is from, or empty for the
+    /// project's compose file. `directory` is the directory of that file, from which extended
+    /// files are read.
+    fn resolve_service(
+        &mut self,
+        services: &mut Mapping,
+        name: &str,
+        file: &Path,
+        directory: &Path,
+    ) -> color_eyre::Result<()> {
+        let entry = (file.to_owned(), name.to_owned());
+        ensure!(!self.stack.contains(&entry), {
+            let cycle: Vec<String> = self
+                .stack
+                .iter()
+                .chain([&entry])
+                .map(|(_, name)| format!("`{name}`"))
+                .collect();
+            format!("`extends` cycle detected: {}", cycle.join(" -> "))
+        });
+
+        let service = services
+            .get_mut(name)
+            .ok_or_else(|| eyre!("service `{name}` does not exist"))?;
+        let Some(extends) = service
+            .as_mapping_mut()
+            .and_then(|service| service.remove("extends"))
+        else {
+            return Ok(());
+        };
+
+        let (base_name, base_file) = match extends {
+            Value::String(service) => (service, None),
+            Value::Mapping(mut extends) => {
+                let service = extends
+                    .remove("service")
+                    .and_then(|service| service.as_str().map(ToOwned::to_owned))
+                    .ok_or_eyre("`extends.service` is required")?;
+                let file = extends
+                    .remove("file")
+                    .map(|file| {
+                        file.as_str()
+                            .map(PathBuf::from)
+                            .ok_or_eyre("`extends.file` must be a string")
+                    })
+                    .transpose()?;
+                (service, file)
+            }
+            _ => bail!("`extends` must be a string or a mapping"),
+        };
+
+        self.stack.push(entry);
+        let base = if let Some(base_file) = base_file {
+            self.extend_from_file(&base_name, &base_file, directory)
+                .wrap_err_with(|| {
+                    format!(
+                        "error extending service `{base_name}` from file `{}`",
+                        base_file.display()
+                    )
+                })
+        } else {
+            self.resolve_service(services, &base_name, file, directory)
+                .map(|()| {
+                    services
+                        .get(base_name.as_str())
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .wrap_err_with(|| format!("error extending service `{base_name}`"))
+        };
+        self.stack.pop();
+        let mut base = base?;
+
+        if let Some(service) = services.get_mut(name) {
+            merge::merge_service(&mut base, service.clone());
+            *service = base;
+        }
+
+        Ok(())
+    }
+
+    /// Read the service `name` from the compose file at `path`, relative to `directory`, and
+    /// resolve its `extends` attribute.
+    ///
+    /// Relative paths of the returned service are rebased onto the directory of `path`.
+    fn extend_from_file(
+        &mut self,
+        name: &str,
+        path: &Path,
+        directory: &Path,
+    ) -> color_eyre::Result<Value> {
+        let full_path = directory.join(path);
+        let canonical_path = fs::canonicalize(&full_path)
+            .wrap_err_with(|| format!("could not find compose file `{}`", full_path.display()))?;
+
+        let ComposeFile { mut value, .. } = read_from_file(&full_path)?;
+        self.interpolator
+            .interpolate(&mut value)
+            .wrap_err("error interpolating compose file")?;
+
+        let services = value
+            .get_mut("services")
+            .and_then(Value::as_mapping_mut)
+            .ok_or_eyre("compose file does not contain `services`")?;
+
+        let file_directory = path.parent().unwrap_or(Path::new(""));
+        self.resolve_service(
+            services,
+            name,
+            &canonical_path,
+            &directory.join(file_directory),
+        )?;
+
+        let mut service = services
+            .remove(name)
+            .ok_or_else(|| eyre!("service `{name}` does not exist"))?;
+        if let Some(service) = service.as_mapping_mut() {
+            rebase_service_paths(service, file_directory);
+        }
+
+        Ok(service)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{super::Variables, *};
+
+    #[test]
+    fn same_file() {
+        let mut value: Value = serde_yaml::from_str(
+            "services:\n  \
+                web:\n    \
+                    extends: base\n    \
+                    command: [web]\n    \
+                    environment:\n      \
+                        TWO: two\n  \
+                base:\n    \
+                    extends:\n      \
+                        service: common\n    \
+                    image: image\n    \
+                    command: [base]\n  \
+                common:\n    \
+                    environment: [ONE=1]\n",
+        )
+        .unwrap();
+
+        let mut interpolator = Interpolator::new(Variables::default(), false);
+        resolve(&mut value, Path::new(""), &mut interpolator).unwrap();
+
+        let expected: Value = serde_yaml::from_str(
+            "services:\n  \
+                web:\n    \
+                    environment:\n      \
+                        ONE: '1'\n      \
+                        TWO: two\n    \
+                    image: image\n    \
+                    command: [web]\n  \
+                base:\n    \
+                    environment: [ONE=1]\n    \
+                    image: image\n    \
+                    command: [base]\n  \
+                common:\n    \
+                    environment: [ONE=1]\n",
+        )
+        .unwrap();
+
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn cycle() {
+        let mut value: Value = serde_yaml::from_str(
+            "services:\n  \
+                one:\n    \
+                    extends: two\n  \
+                two:\n    \
+                    extends: one\n",
+        )
+        .unwrap();
+
+        let mut interpolator = Interpolator::new(Variables::default(), false);
+        assert!(resolve(&mut value, Path::new(""), &mut interpolator).is_err());
+    }
+}
@@ -0,0 +1,465 @@
+//! Support for compose [extensions](https://github.com/compose-spec/compose-spec/blob/master/11-extension.md).
+//!
+//! The `x-podlet` extension sets Quadlet specific options which have no compose equivalent. It is
+//! supported at the top level of the compose file and in services, networks, and volumes. Other
+//! extensions are removed with a warning.
+
+use std::collections::HashMap;
+
+use color_eyre::eyre::{ensure, WrapErr};
+use compose_spec::{Compose, Extensions, Identifier};
+use indexmap::IndexMap;
+use serde::{de::Error, Deserialize, Deserializer};
+use serde_yaml::Value;
+
+use crate::{
+    cli::Unit,
+    quadlet::{self, ExtraOptions},
+};
+
+/// Key of the Podlet compose extension.
+pub const KEY: &str = "x-podlet";
+
+/// Top-level compose fields whose keys are the names of services or resources.
+const NAMED: [&str; 5] = ["services", "networks", "volumes", "configs", "secrets"];
+
+/// Top-level compose fields whose services or resources support the `x-podlet` extension.
+const PODLET_NAMED: [&str; 3] = ["services", "networks", "volumes"];
+
+/// Service fields whose keys are names of other services or resources.
+const SERVICE_NAMED: [&str; 2] = ["depends_on", "networks"];
+
+/// Fields whose keys are user data and not compose fields, such as environment variable names.
+const DATA: [&str; 10] = [
+    "annotations",
+    "args",
+    "aux_addresses",
+    "driver_opts",
+    "environment",
+    "extra_hosts",
+    "labels",
+    "options",
+    "storage_opt",
+    "sysctls",
+];
+
+/// Remove compose extensions, other than `x-podlet` where it is supported, from a compose file,
+/// printing a warning for each.
+pub fn strip_unsupported(compose: &mut Value) {
+    let Value::Mapping(mapping) = compose else {
+        return;
+    };
+
+    retain_supported(mapping, "", true);
+
+    for (key, value) in mapping {
+        let Some(key) = key.as_str().filter(|key| *key != KEY) else {
+            continue;
+        };
+        match value {
+            Value::Mapping(named) if NAMED.contains(&key) => {
+                for (name, value) in named {
+                    let path = format!("{key}.{}", name.as_str().unwrap_or_default());
+                    strip_fields(value, &path, PODLET_NAMED.contains(&key));
+                }
+            }
+            value => strip_fields(value, key, false),
+        }
+    }
+}
+
+/// Remove unsupported extensions from a `value` with compose fields at `path`.
+///
+/// If `podlet` is `true`, the `x-podlet` extension is kept.
+fn strip_fields(value: &mut Value, path: &str, podlet: bool) {
+    match value {
+        Value::Mapping(mapping) => {
+            retain_supported(mapping, path, podlet);
+            for (key, value) in mapping {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                if key == KEY || DATA.contains(&key) {
+                    continue;
+                }
+                let path = format!("{path}.{key}");
+                match value {
+                    Value::Mapping(named) if SERVICE_NAMED.contains(&key) => {
+                        for (name, value) in named {
+                            let path = format!("{path}.{}", name.as_str().unwrap_or_default());
+                            strip_fields(value, &path, false);
+                        }
+                    }
+                    value => strip_fields(value, &path, false),
+                }
+            }
+        }
+        Value::Sequence(sequence) => {
+            for value in sequence {
+                strip_fields(value, path, false);
+            }
+        }
+        Value::Tagged(tagged) => strip_fields(&mut tagged.value, path, podlet),
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+    }
+}
+
+/// Remove extensions from the `mapping` at `path`, keeping `x-podlet` if `podlet` is `true`.
+fn retain_supported(mapping: &mut serde_yaml::Mapping, path: &str, podlet: bool) {
+    mapping.retain(|key, _| {
+        let Some(key) = key.as_str().filter(|key| key.starts_with("x-")) else {
+            return true;
+        };
+        if podlet && key == KEY {
+            return true;
+        }
+        let location = if path.is_empty() {
+            String::from("the top level")
+        } else {
+            format!("`{path}`")
+        };
+        super::warn(format_args!(
+            "ignoring unsupported compose extension `{key}` at {location}"
+        ));
+        false
+    });
+}
+
+/// Ensure the `x-podlet` extension is only used at the top level of the `compose` file.
+///
+/// # Errors
+///
+/// Returns an error if a service, network, or volume has an `x-podlet` extension.
+pub fn ensure_top_level_only(compose: &Compose) -> color_eyre::Result<()> {
+    let services = compose
+        .services
+        .iter()
+        .map(|(name, service)| ("service", name, &service.extensions));
+    let networks = compose.networks.iter().filter_map(|(name, network)| {
+        let network = network.as_ref()?.as_compose()?;
+        Some(("network", name, &network.extensions))
+    });
+    let volumes = compose.volumes.iter().filter_map(|(name, volume)| {
+        let volume = volume.as_ref()?.as_compose()?;
+        Some(("volume", name, &volume.extensions))
+    });
+
+    for (kind, name, extensions) in services.chain(networks).chain(volumes) {
+        ensure!(
+            !extensions.contains_key(KEY),
+            "`x-podlet` of {kind} `{name}` is not supported"
+        );
+    }
+
+    Ok(())
+}
+
+/// Where the `x-podlet` extension is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// The top level of the compose file, applied to all generated Quadlet files.
+    TopLevel,
+    Service,
+    Network,
+    Volume,
+}
+
+/// Options from the `x-podlet` compose extension.
+///
+/// Options in each section may be a single value or a list of values, which are added as repeated
+/// keys.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Podlet {
+    /// Name of the generated Quadlet file, without the extension.
+    name: Option<String>,
+
+    /// Options added to the [Unit] section.
+    #[serde(deserialize_with = "extra_options")]
+    unit: ExtraOptions,
+
+    /// Options added to the [Container] section.
+    #[serde(deserialize_with = "extra_options")]
+    container: ExtraOptions,
+
+    /// Options added to the [Network] section.
+    #[serde(deserialize_with = "extra_options")]
+    network: ExtraOptions,
+
+    /// Options added to the [Volume] section.
+    #[serde(deserialize_with = "extra_options")]
+    volume: ExtraOptions,
+
+    /// Options added to the [Pod] section.
+    #[serde(deserialize_with = "extra_options")]
+    pod: ExtraOptions,
+
+    /// Options added to the [Kube] section.
+    #[serde(deserialize_with = "extra_options")]
+    kube: ExtraOptions,
+
+    /// Options added to the [Service] section.
+    #[serde(deserialize_with = "extra_options")]
+    service: ExtraOptions,
+
+    /// Options added to the [Install] section.
+    #[serde(deserialize_with = "extra_options")]
+    install: ExtraOptions,
+}
+
+impl Podlet {
+    /// Remove the `x-podlet` extension from `extensions` and deserialize it.
+    ///
+    /// Returns the default, which changes nothing, if the extension is not present.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the extension could not be deserialized or it has options which are
+    /// not supported in the given `context`.
+    pub fn take(extensions: &mut Extensions, context: Context) -> color_eyre::Result<Self> {
+        let Some(podlet) = extensions.shift_remove(KEY) else {
+            return Ok(Self::default());
+        };
+        let podlet: Self = serde_yaml::from_value(podlet).wrap_err("invalid `x-podlet`")?;
+
+        let sections = [
+            ("container", &podlet.container, Context::Service),
+            ("network", &podlet.network, Context::Network),
+            ("volume", &podlet.volume, Context::Volume),
+        ];
+        if context == Context::TopLevel {
+            ensure!(
+                podlet.name.is_none(),
+                "`x-podlet.name` is not supported at the top level"
+            );
+        } else {
+            for (section, options, section_context) in sections {
+                ensure!(
+                    options.is_empty() || context == section_context,
+                    "`x-podlet.{section}` is only supported at the top level or in {}s",
+                    section_context.as_str(),
+                );
+            }
+            for (section, options) in [("pod", &podlet.pod), ("kube", &podlet.kube)] {
+                ensure!(
+                    options.is_empty(),
+                    "`x-podlet.{section}` is only supported at the top level"
+                );
+            }
+        }
+
+        Ok(podlet)
+    }
+
+    /// Remove the `x-podlet` extension from each of the compose `services` and deserialize it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a service's extension could not be deserialized or it has options which
+    /// are not supported for services.
+    pub fn take_from_services(
+        services: &mut IndexMap<Identifier, compose_spec::Service>,
+    ) -> color_eyre::Result<HashMap<Identifier, Self>> {
+        services
+            .iter_mut()
+            .map(|(name, service)| {
+                Self::take(&mut service.extensions, Context::Service)
+                    .wrap_err_with(|| format!("error converting service `{name}`"))
+                    .map(|podlet| (name.clone(), podlet))
+            })
+            .collect()
+    }
+
+    /// Custom name of the generated Quadlet file, if set.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Add the options to the sections of the Quadlet `file`.
+    ///
+    /// Options for a resource section are only added if the `file` is of that resource type. The
+    /// file's name is not changed, see [`Podlet::name()`].
+    pub fn apply(&self, file: &mut quadlet::File) {
+        if !self.unit.is_empty() {
+            file.unit
+                .get_or_insert_with(Unit::default)
+                .extend_extra(self.unit.clone());
+        }
+
+        let resource = match file.resource {
+            quadlet::Resource::Container(_) => &self.container,
+            quadlet::Resource::Pod(_) => &self.pod,
+            quadlet::Resource::Kube(_) => &self.kube,
+            quadlet::Resource::Network(_) => &self.network,
+            quadlet::Resource::Volume(_) => &self.volume,
+            // Images are not generated from compose files.
+            quadlet::Resource::Image(_) => &ExtraOptions::default(),
+        };
+        file.globals.extra.extend(resource.clone());
+
+        if !self.service.is_empty() {
+            file.service
+                .get_or_insert_with(Default::default)
+                .extend_extra(self.service.clone());
+        }
+
+        if !self.install.is_empty() {
+            file.install
+                .get_or_insert_with(quadlet::Install::default)
+                .extra
+                .extend(self.install.clone());
+        }
+    }
+}
+
+impl Context {
+    /// Name of the context, as used in error messages.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::TopLevel => "top level",
+            Self::Service => "service",
+            Self::Network => "network",
+            Self::Volume => "volume",
+        }
+    }
+}
+
+/// Deserialize [`ExtraOptions`] from a mapping of keys to a scalar value or a list of scalar
+/// values.
+fn extra_options<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ExtraOptions, D::Error> {
+    let mut extra = ExtraOptions::default();
+    for (key, value) in IndexMap::<String, Value>::deserialize(deserializer)? {
+        match value {
+            Value::Sequence(values) => {
+                for value in values {
+                    extra.push(key.clone(), scalar_to_string(&key, value)?);
+                }
+            }
+            value => extra.push(key.clone(), scalar_to_string(&key, value)?),
+        }
+    }
+    Ok(extra)
+}
+
+/// Convert a scalar YAML `value` for the option `key` into a [`String`].
+fn scalar_to_string<E: Error>(key: &str, value: Value) -> Result<String, E> {
+    match value {
+        Value::String(value) => Ok(value),
+        Value::Bool(value) => Ok(value.to_string()),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Null | Value::Sequence(_) | Value::Mapping(_) | Value::Tagged(_) => Err(E::custom(
+            format!("value of `{key}` must be a string, number, or boolean"),
+        )),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip() {
+        let mut compose: Value = serde_yaml::from_str(
+            "x-anchors: {}\n\
+            x-podlet: {}\n\
+            services:\n  \
+                x-service:\n    \
+                    image: image\n    \
+                    x-podlet: {}\n    \
+                    x-other: {}\n    \
+                    environment:\n      \
+                        x-var: value\n    \
+                    ports:\n      \
+                        - target: 80\n        \
+                          x-port: {}\n\
+            secrets:\n  \
+                secret:\n    \
+                    file: secret\n    \
+                    x-podlet: {}\n",
+        )
+        .unwrap();
+        strip_unsupported(&mut compose);
+
+        let expected: Value = serde_yaml::from_str(
+            "x-podlet: {}\n\
+            services:\n  \
+                x-service:\n    \
+                    image: image\n    \
+                    x-podlet: {}\n    \
+                    environment:\n      \
+                        x-var: value\n    \
+                    ports:\n      \
+                        - target: 80\n\
+            secrets:\n  \
+                secret:\n    \
+                    file: secret\n",
+        )
+        .unwrap();
+        assert_eq!(compose, expected);
+    }
+
+    #[test]
+    fn apply() {
+        let mut extensions: Extensions = serde_yaml::from_str(
+            "x-podlet:\n  \
+                name: renamed\n  \
+                unit:\n    \
+                    Description: test\n  \
+                container:\n    \
+                    AutoUpdate: registry\n    \
+                    Notify: true\n  \
+                service:\n    \
+                    ExecStartPost: [one, two]\n",
+        )
+        .unwrap();
+        let podlet = Podlet::take(&mut extensions, Context::Service).unwrap();
+        assert!(extensions.is_empty());
+        assert_eq!(podlet.name(), Some("renamed"));
+
+        let mut file = quadlet::File {
+            name: String::from("name"),
+            unit: None,
+            resource: quadlet::Container {
+                image: String::from("image"),
+                ..quadlet::Container::default()
+            }
+            .into(),
+            globals: quadlet::Globals::default(),
+            service: None,
+            install: None,
+        };
+        podlet.apply(&mut file);
+
+        assert_eq!(
+            file.to_string(),
+            "[Unit]\n\
+            Description=test\n\
+            \n\
+            [Container]\n\
+            Image=image\n\
+            AutoUpdate=registry\n\
+            Notify=true\n\
+            \n\
+            [Service]\n\
+            ExecStartPost=one\n\
+            ExecStartPost=two\n"
+        );
+    }
+
+    #[test]
+    fn context() {
+        let mut extensions: Extensions =
+            serde_yaml::from_str("x-podlet: {network: {Internal: true}}").unwrap();
+        assert!(Podlet::take(&mut extensions.clone(), Context::Service).is_err());
+        assert!(Podlet::take(&mut extensions.clone(), Context::TopLevel).is_ok());
+        assert!(Podlet::take(&mut extensions, Context::Network).is_ok());
+
+        let mut extensions: Extensions = serde_yaml::from_str("x-podlet: {name: name}").unwrap();
+        assert!(Podlet::take(&mut extensions, Context::TopLevel).is_err());
+
+        let mut extensions: Extensions =
+            serde_yaml::from_str("x-podlet: {unit: {After: {a: b}}}").unwrap();
+        assert!(Podlet::take(&mut extensions, Context::Service).is_err());
+    }
+}
@@ -0,0 +1,419 @@
+//! Resolution of the compose top-level
+//! [`include`](https://github.com/compose-spec/compose-spec/blob/master/14-include.md) element.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, ensure, eyre, OptionExt, WrapErr};
+use compose_spec::{Include, ShortOrLong};
+use path_clean::PathClean;
+use serde_yaml::{Mapping, Value};
+
+use super::{interpolate::Interpolator, merge, read_from_file, ComposeFile, Variables};
+
+/// Top-level compose elements which contain named resources.
+const RESOURCES: [&str; 5] = ["services", "networks", "volumes", "configs", "secrets"];
+
+/// Resolve and remove the `include` entries of the compose file `value`.
+///
+/// Each included compose file is read, interpolated with its own environment files, merged with
+/// the other paths of its entry, and has its relative paths rebased, before its resources are
+/// added to `value`.
+///
+/// `project_directory` is the directory relative paths in `value` are resolved from. `stack`
+/// contains the canonical paths of the compose files currently being read and is used to detect
+/// include cycles.
+///
+/// # Errors
+///
+/// Returns an error if an `include` entry is invalid, an included file could not be read or
+/// interpolated, an include cycle is detected, or an included resource conflicts with an existing
+/// resource.
+pub fn resolve(
+    value: &mut Value,
+    project_directory: &Path,
+    interpolator: &mut Interpolator,
+    stack: &mut Vec<PathBuf>,
+) -> color_eyre::Result<()> {
+    let Some(mapping) = value.as_mapping_mut() else {
+        return Ok(());
+    };
+    let Some(include) = mapping.remove("include") else {
+        return Ok(());
+    };
+
+    let include: Vec<ShortOrLong<PathBuf, Include>> =
+        serde_yaml::from_value(include).wrap_err("invalid `include`")?;
+
+    for include in include {
+        let include = include.into_long();
+        let paths = include
+            .path
+            .clone()
+            .into_list()
+            .iter()
+            .map(|path| format!("`{}`", path.display()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let included = load(include, project_directory, interpolator, stack)
+            .wrap_err_with(|| format!("error including {paths}"))?;
+        add_resources(mapping, included)
+            .wrap_err_with(|| format!("error adding resources included from {paths}"))?;
+    }
+
+    Ok(())
+}
+
+/// Load an included compose project.
+///
+/// The returned compose file has its own `include` entries resolved and its relative paths
+/// rebased to be relative to `project_directory`, the project directory of the including file.
+fn load(
+    Include {
+        path,
+        project_directory: include_directory,
+        env_file,
+    }: Include,
+    project_directory: &Path,
+    interpolator: &mut Interpolator,
+    stack: &mut Vec<PathBuf>,
+) -> color_eyre::Result<Mapping> {
+    let paths = path.into_list();
+
+    // Relative paths in the included files are relative to its project directory, which defaults
+    // to the directory of the first file.
+    let relative_directory = match include_directory {
+        Some(directory) => PathBuf::from(directory),
+        None => paths
+            .first()
+            .ok_or_eyre("`path` is empty")?
+            .parent()
+            .map(Path::to_owned)
+            .unwrap_or_default(),
+    };
+    let included_project_directory = project_directory.join(&relative_directory);
+
+    let env_files: Vec<PathBuf> = env_file
+        .map(|env_file| {
+            env_file
+                .into_list()
+                .into_iter()
+                .map(|path| project_directory.join(path))
+                .collect()
+        })
+        .unwrap_or_default();
+    let variables = Variables::from_env_files(&env_files, &included_project_directory)?;
+
+    let previous = interpolator.replace_variables(variables);
+    let result = load_files(
+        &paths,
+        project_directory,
+        &included_project_directory,
+        interpolator,
+        stack,
+    );
+    interpolator.replace_variables(previous);
+    let mut value = result?;
+
+    rebase_paths(&mut value, &relative_directory);
+
+    match value {
+        Value::Mapping(mapping) => Ok(mapping),
+        Value::Null => Ok(Mapping::new()),
+        _ => bail!("included compose file is not a mapping"),
+    }
+}
+
+/// Read, interpolate, and merge the compose files at `paths`, relative to `project_directory`,
+/// and resolve their `include` entries.
+fn load_files<'a>(
+    paths: impl IntoIterator<Item = &'a PathBuf>,
+    project_directory: &Path,
+    included_project_directory: &Path,
+    interpolator: &mut Interpolator,
+    stack: &mut Vec<PathBuf>,
+) -> color_eyre::Result<Value> {
+    let mut canonical_paths = Vec::new();
+    let mut merged = Value::Null;
+
+    for path in paths {
+        let path = project_directory.join(path);
+        let canonical_path = fs::canonicalize(&path)
+            .wrap_err_with(|| format!("could not find compose file `{}`", path.display()))?;
+
+        ensure!(!stack.contains(&canonical_path), {
+            let cycle: Vec<String> = stack
+                .iter()
+                .chain([&canonical_path])
+                .map(|path| format!("`{}`", path.display()))
+                .collect();
+            format!("include cycle detected: {}", cycle.join(" -> "))
+        });
+
+        let ComposeFile { path, mut value } = read_from_file(&path)?;
+        interpolator.interpolate(&mut value).wrap_err_with(|| {
+            format!(
+                "error interpolating compose file `{}`",
+                path.unwrap_or_default().display()
+            )
+        })?;
+
+        if merged.is_null() {
+            merged = value;
+        } else {
+            merge::merge(&mut merged, value);
+        }
+        canonical_paths.push(canonical_path);
+    }
+
+    let stack_len = stack.len();
+    stack.extend(canonical_paths);
+    let result = resolve(&mut merged, included_project_directory, interpolator, stack);
+    stack.truncate(stack_len);
+    result?;
+
+    Ok(merged)
+}
+
+/// Add the resources from the `included` compose file to `mapping`.
+///
+/// # Errors
+///
+/// Returns an error if an included resource has the same name as, but is different from, an
+/// existing resource.
+fn add_resources(mapping: &mut Mapping, included: Mapping) -> color_eyre::Result<()> {
+    for (key, value) in included {
+        let Some(resources_key) = key.as_str().filter(|key| RESOURCES.contains(key)) else {
+            // Extensions are added if not already present.
+            if key.as_str().is_some_and(|key| key.starts_with("x-")) && !mapping.contains_key(&key)
+            {
+                mapping.insert(key, value);
+            }
+            continue;
+        };
+
+        let Value::Mapping(included) = value else {
+            continue;
+        };
+        let resources = mapping
+            .entry(key.clone())
+            .or_insert_with(|| Mapping::new().into());
+        if resources.is_null() {
+            *resources = Mapping::new().into();
+        }
+        let resources = resources
+            .as_mapping_mut()
+            .ok_or_else(|| eyre!("`{resources_key}` must be a mapping"))?;
+
+        for (name, resource) in included {
+            if let Some(existing) = resources.get(&name) {
+                ensure!(
+                    *existing == resource,
+                    "`{resources_key}.{}` conflicts with an existing definition",
+                    name.as_str().unwrap_or_default()
+                );
+            } else {
+                resources.insert(name, resource);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Rebase the relative paths in the compose file `value` onto `directory`.
+///
+/// Rebased fields are a service's `build` context, `env_file`, bind mount `volumes`, and
+/// `extends.file`, as well as the `file` of top-level `configs` and `secrets`.
+pub fn rebase_paths(value: &mut Value, directory: &Path) {
+    if directory.as_os_str().is_empty() || directory == Path::new(".") {
+        return;
+    }
+
+    let Some(mapping) = value.as_mapping_mut() else {
+        return;
+    };
+
+    if let Some(Value::Mapping(services)) = mapping.get_mut("services") {
+        for service in services.values_mut() {
+            if let Some(service) = service.as_mapping_mut() {
+                rebase_service_paths(service, directory);
+            }
+        }
+    }
+
+    for key in ["configs", "secrets"] {
+        if let Some(Value::Mapping(resources)) = mapping.get_mut(key) {
+            for resource in resources.values_mut() {
+                if let Some(Value::String(file)) = resource.get_mut("file") {
+                    *file = rebase(directory, file);
+                }
+            }
+        }
+    }
+}
+
+/// Rebase the relative paths in a compose `service` onto `directory`.
+pub fn rebase_service_paths(service: &mut Mapping, directory: &Path) {
+    if directory.as_os_str().is_empty() || directory == Path::new(".") {
+        return;
+    }
+
+    match service.get_mut("build") {
+        Some(Value::String(context)) => rebase_build_context(context, directory),
+        Some(Value::Mapping(build)) => {
+            if let Some(Value::String(context)) = build.get_mut("context") {
+                rebase_build_context(context, directory);
+            }
+        }
+        _ => {}
+    }
+
+    match service.get_mut("env_file") {
+        Some(Value::String(env_file)) => *env_file = rebase(directory, env_file),
+        Some(Value::Sequence(env_files)) => {
+            for env_file in env_files {
+                match env_file {
+                    Value::String(env_file) => *env_file = rebase(directory, env_file),
+                    Value::Mapping(env_file) => {
+                        if let Some(Value::String(path)) = env_file.get_mut("path") {
+                            *path = rebase(directory, path);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Sequence(volumes)) = service.get_mut("volumes") {
+        for volume in volumes {
+            match volume {
+                Value::String(volume) => {
+                    if let Some((source, rest)) = volume.split_once(':') {
+                        if source.starts_with('.') {
+                            *volume = format!("{}:{rest}", rebase(directory, source));
+                        }
+                    }
+                }
+                Value::Mapping(volume)
+                    if volume.get("type").and_then(Value::as_str) == Some("bind") =>
+                {
+                    if let Some(Value::String(source)) = volume.get_mut("source") {
+                        *source = rebase(directory, source);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(Value::String(file)) = service
+        .get_mut("extends")
+        .and_then(|extends| extends.get_mut("file"))
+    {
+        *file = rebase(directory, file);
+    }
+}
+
+/// Rebase a build `context` onto `directory`, unless it is a URL.
+fn rebase_build_context(context: &mut String, directory: &Path) {
+    if !context.contains("://") && !context.starts_with("git@") {
+        *context = rebase(directory, context);
+    }
+}
+
+/// Rebase a relative `path` onto `directory`.
+///
+/// Absolute paths and paths starting with `~` are returned unchanged. Rebased paths start with
+/// `./` or `../` so they are still recognized as relative paths in the short syntax of `volumes`.
+fn rebase(directory: &Path, path: &str) -> String {
+    if Path::new(path).is_absolute() || path.starts_with('~') {
+        return path.to_owned();
+    }
+
+    let path = directory.join(path).clean();
+    if path.is_absolute() || path.starts_with("..") {
+        path.display().to_string()
+    } else {
+        Path::new(".").join(path).display().to_string()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebase_relative_paths() {
+        let mut value: Value = serde_yaml::from_str(
+            "services:\n  \
+                web:\n    \
+                    build: .\n    \
+                    env_file: [web.env]\n    \
+                    volumes:\n      \
+                        - ./data:/data:Z\n      \
+                        - ../shared:/shared\n      \
+                        - /absolute:/absolute\n      \
+                        - named:/named\n      \
+                        - type: bind\n        \
+                          source: ./config\n        \
+                          target: /config\n\
+            secrets:\n  \
+                secret:\n    \
+                    file: secret.txt\n",
+        )
+        .unwrap();
+
+        rebase_paths(&mut value, Path::new("sub"));
+
+        let expected: Value = serde_yaml::from_str(
+            "services:\n  \
+                web:\n    \
+                    build: ./sub\n    \
+                    env_file: [./sub/web.env]\n    \
+                    volumes:\n      \
+                        - ./sub/data:/data:Z\n      \
+                        - ./shared:/shared\n      \
+                        - /absolute:/absolute\n      \
+                        - named:/named\n      \
+                        - type: bind\n        \
+                          source: ./sub/config\n        \
+                          target: /config\n\
+            secrets:\n  \
+                secret:\n    \
+                    file: ./sub/secret.txt\n",
+        )
+        .unwrap();
+
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn resource_conflict() {
+        let mut mapping: Mapping =
+            serde_yaml::from_str("services:\n  web:\n    image: one\n").unwrap();
+
+        let same: Mapping = serde_yaml::from_str("services:\n  web:\n    image: one\n").unwrap();
+        add_resources(&mut mapping, same).unwrap();
+
+        let other: Mapping = serde_yaml::from_str("services:\n  db:\n    image: db\n").unwrap();
+        add_resources(&mut mapping, other).unwrap();
+        assert_eq!(
+            mapping
+                .get("services")
+                .and_then(Value::as_mapping)
+                .map(Mapping::len),
+            Some(2)
+        );
+
+        let conflict: Mapping =
+            serde_yaml::from_str("services:\n  web:\n    image: two\n").unwrap();
+        assert!(add_resources(&mut mapping, conflict).is_err());
+    }
+}
@@ -0,0 +1,920 @@
+//! Compose file [interpolation](https://github.com/compose-spec/compose-spec/blob/master/12-interpolation.md)
+//! of variables from environment files and the process environment.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    env, fs, mem,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    eyre::{bail, ensure, eyre, WrapErr},
+    Help,
+};
+use indexmap::{IndexMap, IndexSet};
+use serde_yaml::Value;
+
+/// Variables available for interpolation.
+///
+/// Variables set in the process environment take precedence over those read from environment
+/// files.
+#[derive(Debug, Default, Clone)]
+pub struct Variables {
+    /// Variables read from environment files.
+    values: HashMap<String, String>,
+
+    /// Paths of the environment files which were read.
+    env_files: Vec<PathBuf>,
+}
+
+impl Variables {
+    /// Read variables from `env_files`, or, if empty, from the `.env` file in `project_dir` if it
+    /// exists.
+    ///
+    /// Variables from later files override those from earlier files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if one of the `env_files` could not be read or parsed.
+    pub fn from_env_files(env_files: &[PathBuf], project_dir: &Path) -> color_eyre::Result<Self> {
+        let mut variables = Self::default();
+
+        if env_files.is_empty() {
+            let path = project_dir.join(".env");
+            if path.is_file() {
+                variables.read_env_file(path)?;
+            }
+        } else {
+            for path in env_files {
+                variables.read_env_file(path.clone())?;
+            }
+        }
+
+        Ok(variables)
+    }
+
+    /// Consume the variables, returning those read from environment files.
+    pub fn into_values(self) -> HashMap<String, String> {
+        self.values
+    }
+
+    /// Get the value of the variable `name`.
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        env::var(name)
+            .ok()
+            .map(Cow::Owned)
+            .or_else(|| self.values.get(name).map(|value| value.as_str().into()))
+    }
+
+    /// Returns `true` if the variable `name` was set in an environment file.
+    fn in_env_file(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// Read and parse the environment file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be read or there was an error parsing it.
+    fn read_env_file(&mut self, path: PathBuf) -> color_eyre::Result<()> {
+        let contents = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("could not read environment file `{}`", path.display()))?;
+        self.parse_env_file(&contents)
+            .wrap_err_with(|| format!("error parsing environment file `{}`", path.display()))?;
+        self.env_files.push(path);
+        Ok(())
+    }
+
+    /// Parse the `contents` of an environment file, adding each variable to `self`.
+    ///
+    /// Each line is in the form `[export ]NAME=VALUE`. Values may be single quoted, which are
+    /// taken literally, or double quoted, which support escape sequences. Unquoted and double
+    /// quoted values are interpolated. Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a line is invalid or a value could not be interpolated.
+    fn parse_env_file(&mut self, contents: &str) -> color_eyre::Result<()> {
+        for (line, number) in contents.lines().zip(1..) {
+            let line = line.trim_start();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+
+            let Some((name, value)) = line.split_once('=') else {
+                // `NAME` alone takes the value from the process environment, which always has
+                // precedence anyway.
+                continue;
+            };
+            let name = name.trim();
+            ensure!(
+                !name.is_empty() && !name.contains(char::is_whitespace),
+                "invalid variable name `{name}` on line {number}"
+            );
+
+            let value = self
+                .parse_env_file_value(value.trim_start())
+                .wrap_err_with(|| format!("error parsing value of `{name}` on line {number}"))?;
+            self.values.insert(name.to_owned(), value);
+        }
+
+        Ok(())
+    }
+
+    /// Parse and interpolate a `value` from a line of an environment file.
+    fn parse_env_file_value(&self, value: &str) -> color_eyre::Result<String> {
+        if let Some(value) = value.strip_prefix('\'') {
+            let (value, rest) = value
+                .split_once('\'')
+                .ok_or_else(|| eyre!("missing closing single quote (')"))?;
+            ensure_comment(rest)?;
+            Ok(value.to_owned())
+        } else if let Some(value) = value.strip_prefix('"') {
+            let mut unescaped = String::with_capacity(value.len());
+            let mut chars = value.chars();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => unescaped.push('\n'),
+                        Some('r') => unescaped.push('\r'),
+                        Some('t') => unescaped.push('\t'),
+                        Some(char) => unescaped.push(char),
+                        None => bail!("missing closing double quote (\")"),
+                    },
+                    Some(char) => unescaped.push(char),
+                    None => bail!("missing closing double quote (\")"),
+                }
+            }
+            ensure_comment(chars.as_str())?;
+            substitute(&unescaped, self, &mut IndexSet::new())
+        } else {
+            // Inline comments must be preceded by whitespace.
+            let value = value
+                .find(" #")
+                .or_else(|| value.find("\t#"))
+                .map_or(value, |index| value.split_at(index).0);
+            substitute(value.trim_end(), self, &mut IndexSet::new())
+        }
+    }
+}
+
+/// Ensure that the `rest` of a line after a quoted value is empty or a comment.
+fn ensure_comment(rest: &str) -> color_eyre::Result<()> {
+    let rest = rest.trim_start();
+    ensure!(
+        rest.is_empty() || rest.starts_with('#'),
+        "unexpected characters after closing quote: `{rest}`"
+    );
+    Ok(())
+}
+
+/// Interpolates variables in compose files.
+///
+/// Variables are either substituted with their value or, if `keep` is `true`, kept as
+/// `${VARIABLE}` references so they can be expanded by systemd from the environment of the
+/// generated service.
+#[derive(Debug)]
+pub struct Interpolator {
+    variables: Variables,
+
+    /// Paths of all environment files used for interpolation.
+    env_files: IndexSet<PathBuf>,
+
+    /// Keep variables as references instead of substituting them.
+    keep: bool,
+
+    /// Variables kept as references, and their default values, if any.
+    kept: IndexMap<String, Option<String>>,
+
+    /// Variables which were not set and were substituted with an empty string.
+    unset: IndexSet<String>,
+}
+
+impl Interpolator {
+    /// Create a new [`Interpolator`] using `variables`.
+    ///
+    /// If `keep` is `true`, variables are kept as references instead of being substituted.
+    pub fn new(variables: Variables, keep: bool) -> Self {
+        Self {
+            env_files: variables.env_files.iter().cloned().collect(),
+            variables,
+            keep,
+            kept: IndexMap::new(),
+            unset: IndexSet::new(),
+        }
+    }
+
+    /// Replace the variables used for interpolation, returning the previous variables.
+    ///
+    /// Used for interpolating included compose files, which have their own environment files.
+    pub fn replace_variables(&mut self, variables: Variables) -> Variables {
+        self.env_files.extend(variables.env_files.iter().cloned());
+        mem::replace(&mut self.variables, variables)
+    }
+
+    /// Paths of all environment files used for interpolation.
+    pub fn env_files(&self) -> &IndexSet<PathBuf> {
+        &self.env_files
+    }
+
+    /// Variables kept as references, and their default values, if any.
+    pub fn kept(&self) -> &IndexMap<String, Option<String>> {
+        &self.kept
+    }
+
+    /// Get the value of the variable `name` from Podlet's environment or the environment files.
+    pub fn variable(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.variables.get(name)
+    }
+
+    /// Interpolate all strings within the compose file `value`.
+    ///
+    /// Mapping keys are not interpolated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a string contains an invalid variable reference, a required variable is
+    /// not set, or a variable is kept with conflicting default values.
+    pub fn interpolate(&mut self, value: &mut Value) -> color_eyre::Result<()> {
+        self.interpolate_value(value, "")
+    }
+
+    /// Recursively interpolate `value`, which is at `path` within the compose file.
+    fn interpolate_value(&mut self, value: &mut Value, path: &str) -> color_eyre::Result<()> {
+        match value {
+            Value::String(string) => {
+                if !string.contains('$') {
+                    return Ok(());
+                }
+
+                let interpolated = self
+                    .interpolate_str(string)
+                    .wrap_err_with(|| format!("error interpolating `{path}`"))?;
+
+                // Substituted values of fields which are not strings are converted to numbers or
+                // booleans, like Docker Compose does using the compose schema. Other values stay
+                // strings, so that e.g. `1.10` is not changed to `1.1`.
+                let field_type = if self.keep { None } else { field_type(path) };
+                let typed = match field_type {
+                    Some(FieldType::Typed) => serde_yaml::from_str(&interpolated)
+                        .ok()
+                        .filter(|typed| matches!(typed, Value::Number(_) | Value::Bool(_))),
+                    Some(FieldType::Mode) => octal_mode(&interpolated).map(Into::into),
+                    None => None,
+                };
+                *value = typed.unwrap_or(Value::String(interpolated));
+            }
+            Value::Sequence(sequence) => {
+                for (index, value) in sequence.iter_mut().enumerate() {
+                    self.interpolate_value(value, &format!("{path}[{index}]"))?;
+                }
+            }
+            Value::Mapping(mapping) => {
+                for (key, value) in mapping {
+                    let path = match key {
+                        Value::String(key) if path.is_empty() => key.clone(),
+                        Value::String(key) => format!("{path}.{key}"),
+                        key => format!("{path}.{key:?}"),
+                    };
+                    self.interpolate_value(value, &path)?;
+                }
+            }
+            Value::Tagged(tagged) => self.interpolate_value(&mut tagged.value, path)?,
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Interpolate a single string.
+    fn interpolate_str(&mut self, input: &str) -> color_eyre::Result<String> {
+        if self.keep {
+            keep(input, &mut self.kept)
+        } else {
+            let mut unset = IndexSet::new();
+            let output = substitute(input, &self.variables, &mut unset)?;
+            for name in unset {
+                if !self.unset.contains(&name) {
+                    super::warn(format_args!(
+                        "the `{name}` variable is not set, defaulting to a blank string"
+                    ));
+                    self.unset.insert(name);
+                }
+            }
+            Ok(output)
+        }
+    }
+
+    /// Names of kept variables which have no default value and are not set in an environment
+    /// file.
+    pub fn kept_without_value(&self) -> impl Iterator<Item = &str> {
+        self.kept
+            .iter()
+            .filter(|(name, default)| default.is_none() && !self.variables.in_env_file(name))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Fields of a compose file which are numbers or booleans.
+const TYPED_FIELDS: &[&str] = &[
+    "attachable",
+    "cpu_count",
+    "cpu_percent",
+    "cpu_period",
+    "cpu_quota",
+    "cpu_rt_period",
+    "cpu_rt_runtime",
+    "cpu_shares",
+    "cpus",
+    "create_host_path",
+    "disable",
+    "enable_ipv6",
+    "external",
+    "hard",
+    "init",
+    "internal",
+    "max_attempts",
+    "max_replicas_per_node",
+    "nocopy",
+    "oom_kill_disable",
+    "oom_score_adj",
+    "parallelism",
+    "pids",
+    "pids_limit",
+    "privileged",
+    "read_only",
+    "replicas",
+    "required",
+    "restart",
+    "retries",
+    "scale",
+    "soft",
+    "stdin_open",
+    "tty",
+    "weight",
+];
+
+/// Fields of a compose file which are maps with arbitrary keys and string values.
+const STRING_MAPS: &[&str] = &[
+    "annotations",
+    "args",
+    "driver_opts",
+    "environment",
+    "extra_hosts",
+    "labels",
+    "options",
+    "sysctls",
+];
+
+/// Type of a field within a compose file which is not a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldType {
+    /// A number or boolean.
+    Typed,
+
+    /// A file mode, which is a number written in octal.
+    Mode,
+}
+
+/// The type of the value at `path` within a compose file, or [`None`] if it is a string.
+///
+/// Values in [`STRING_MAPS`] and extensions are always strings, even if their key is in
+/// [`TYPED_FIELDS`]. Values in `ulimits` are numbers. The `mode` of a service's `configs`,
+/// `secrets`, and `tmpfs` volume options is a file mode, but the `mode` of `deploy` is a string.
+fn field_type(path: &str) -> Option<FieldType> {
+    let mut segments = path.split('.').map(|segment| {
+        segment
+            .split_once('[')
+            .map_or(segment, |(segment, _)| segment)
+    });
+    let field = segments.next_back()?;
+
+    let mut parent = None;
+    for (index, segment) in segments.enumerate() {
+        // The first two segments are the top-level field and the name of a service, network, etc.
+        if segment.starts_with("x-") || (index >= 2 && STRING_MAPS.contains(&segment)) {
+            return None;
+        }
+        parent = Some(segment);
+    }
+
+    if field == "mode" && matches!(parent, Some("configs" | "secrets" | "tmpfs")) {
+        Some(FieldType::Mode)
+    } else if TYPED_FIELDS.contains(&field) || parent == Some("ulimits") {
+        Some(FieldType::Typed)
+    } else {
+        None
+    }
+}
+
+/// Parse a substituted file mode as an octal number, with or without a `0o` prefix, like Docker
+/// Compose does, e.g. "0440" becomes 288.
+fn octal_mode(mode: &str) -> Option<u32> {
+    u32::from_str_radix(mode.strip_prefix("0o").unwrap_or(mode), 8).ok()
+}
+
+/// Substitute the variables referenced in `input` with their values from `variables`.
+///
+/// The names of variables which are referenced without a default value and are not set are added
+/// to `unset`.
+///
+/// # Errors
+///
+/// Returns an error if `input` contains an invalid variable reference or a required variable is
+/// not set.
+fn substitute(
+    input: &str,
+    variables: &Variables,
+    unset: &mut IndexSet<String>,
+) -> color_eyre::Result<String> {
+    let mut output = String::with_capacity(input.len());
+
+    for token in tokenize(input)? {
+        match token {
+            Token::Literal(literal) => output.push_str(literal),
+            Token::Dollar => output.push('$'),
+            Token::Variable(Variable { name, modifier }) => {
+                let value = variables.get(name);
+                let Some(Modifier { kind, empty, word }) = modifier else {
+                    if let Some(value) = value {
+                        output.push_str(&value);
+                    } else {
+                        unset.insert(name.to_owned());
+                    }
+                    continue;
+                };
+
+                // Whether the variable is considered set for the purposes of the modifier.
+                let set = value
+                    .as_deref()
+                    .is_some_and(|value| !(empty && value.is_empty()));
+
+                match (kind, set) {
+                    (ModifierKind::Default | ModifierKind::Required, true) => {
+                        output.push_str(value.as_deref().unwrap_or_default());
+                    }
+                    (ModifierKind::Default, false) | (ModifierKind::Alternative, true) => {
+                        output.push_str(&substitute(word, variables, unset)?);
+                    }
+                    (ModifierKind::Required, false) => {
+                        let error = if value.is_some() {
+                            eyre!("required variable `{name}` is empty")
+                        } else {
+                            eyre!("required variable `{name}` is not set")
+                        };
+                        let message = substitute(word, variables, unset)?;
+                        return Err(if message.is_empty() {
+                            error
+                        } else {
+                            error.wrap_err(message)
+                        }
+                        .suggestion(
+                            "set the variable in the environment or an environment file, \
+                                see `--env-file`",
+                        ));
+                    }
+                    (ModifierKind::Alternative, false) => {}
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Replace the variable references in `input` with `${VARIABLE}`, which systemd will expand.
+///
+/// Kept variables and their default values are added to `kept`.
+///
+/// # Errors
+///
+/// Returns an error if `input` contains an invalid variable reference, a reference can not be
+/// expressed for systemd, or a variable is given conflicting default values.
+fn keep(input: &str, kept: &mut IndexMap<String, Option<String>>) -> color_eyre::Result<String> {
+    let mut output = String::with_capacity(input.len());
+
+    for token in tokenize(input)? {
+        match token {
+            Token::Literal(literal) => output.push_str(literal),
+            // systemd also uses `$$` for a literal `$`.
+            Token::Dollar => output.push_str("$$"),
+            Token::Variable(Variable { name, modifier }) => {
+                let default = match modifier {
+                    None
+                    | Some(Modifier {
+                        kind: ModifierKind::Required,
+                        ..
+                    }) => None,
+                    Some(Modifier {
+                        kind: ModifierKind::Default,
+                        word,
+                        ..
+                    }) => Some(literal_word(word)?),
+                    Some(Modifier {
+                        kind: ModifierKind::Alternative,
+                        ..
+                    }) => bail!(
+                        "alternative values (`${{{name}:+...}}`) are not supported \
+                            when keeping variables"
+                    ),
+                };
+
+                match (kept.get_mut(name), default) {
+                    (Some(Some(existing)), Some(default)) => ensure!(
+                        *existing == default,
+                        "variable `{name}` has conflicting default values \
+                            `{existing}` and `{default}`"
+                    ),
+                    (Some(existing @ None), default) => *existing = default,
+                    (Some(Some(_)), None) => {}
+                    (None, default) => {
+                        kept.insert(name.to_owned(), default);
+                    }
+                }
+
+                output.push_str("${");
+                output.push_str(name);
+                output.push('}');
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Convert the `word` of a [`Modifier`] into a literal string.
+///
+/// # Errors
+///
+/// Returns an error if the `word` contains variable references.
+fn literal_word(word: &str) -> color_eyre::Result<String> {
+    tokenize(word)?
+        .into_iter()
+        .map(|token| match token {
+            Token::Literal(literal) => Ok(literal),
+            Token::Dollar => Ok("$"),
+            Token::Variable(_) => Err(eyre!(
+                "nested variables in default values (`{word}`) are not supported \
+                    when keeping variables"
+            )),
+        })
+        .collect()
+}
+
+/// A token of a string being interpolated.
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    /// Literal part of the string.
+    Literal(&'a str),
+    /// An escaped dollar sign (`$$`).
+    Dollar,
+    /// A variable reference, `$NAME` or `${NAME}`, with an optional modifier.
+    Variable(Variable<'a>),
+}
+
+/// A variable reference.
+#[derive(Debug, PartialEq, Eq)]
+struct Variable<'a> {
+    name: &'a str,
+    modifier: Option<Modifier<'a>>,
+}
+
+/// A modifier of a braced variable reference, e.g. `${NAME:-default}`.
+#[derive(Debug, PartialEq, Eq)]
+struct Modifier<'a> {
+    kind: ModifierKind,
+
+    /// Whether the modifier treats an empty variable the same as an unset one (`:` prefix).
+    empty: bool,
+
+    /// The word following the modifier, interpolated only when used.
+    word: &'a str,
+}
+
+/// The kind of a [`Modifier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModifierKind {
+    /// `-`, use the word if the variable is not set.
+    Default,
+    /// `?`, error with the word as a message if the variable is not set.
+    Required,
+    /// `+`, use the word if the variable is set.
+    Alternative,
+}
+
+/// Split `input` into [`Token`]s.
+///
+/// # Errors
+///
+/// Returns an error if a braced variable reference is invalid or not terminated.
+fn tokenize(mut input: &str) -> color_eyre::Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+
+    while let Some((literal, rest)) = input.split_once('$') {
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+
+        input = if let Some(rest) = rest.strip_prefix('$') {
+            tokens.push(Token::Dollar);
+            rest
+        } else if let Some(rest) = rest.strip_prefix('{') {
+            let (variable, rest) = parse_braced(rest)?;
+            tokens.push(Token::Variable(variable));
+            rest
+        } else {
+            let (name, rest) = split_name(rest);
+            if name.is_empty() {
+                tokens.push(Token::Literal("$"));
+            } else {
+                tokens.push(Token::Variable(Variable {
+                    name,
+                    modifier: None,
+                }));
+            }
+            rest
+        };
+    }
+
+    if !input.is_empty() {
+        tokens.push(Token::Literal(input));
+    }
+
+    Ok(tokens)
+}
+
+/// Parse a braced variable reference from `input`, which follows the opening `${`.
+///
+/// Returns the [`Variable`] and the rest of the input after the closing `}`.
+///
+/// # Errors
+///
+/// Returns an error if the reference is invalid or not terminated.
+fn parse_braced(input: &str) -> color_eyre::Result<(Variable<'_>, &str)> {
+    let (name, rest) = split_name(input);
+    ensure!(!name.is_empty(), "invalid variable reference `${{{input}`");
+
+    if let Some(rest) = rest.strip_prefix('}') {
+        return Ok((
+            Variable {
+                name,
+                modifier: None,
+            },
+            rest,
+        ));
+    }
+
+    let (empty, rest) = rest
+        .strip_prefix(':')
+        .map_or((false, rest), |rest| (true, rest));
+    let mut chars = rest.chars();
+    let kind = match chars.next() {
+        Some('-') => ModifierKind::Default,
+        Some('?') => ModifierKind::Required,
+        Some('+') => ModifierKind::Alternative,
+        _ => bail!("invalid variable reference `${{{input}`, unknown modifier"),
+    };
+    let rest = chars.as_str();
+
+    // Find the closing brace, skipping nested references.
+    let mut depth = 0_usize;
+    let mut end = None;
+    let mut chars = rest.char_indices().peekable();
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '$' => {
+                if chars.next_if(|(_, char)| *char == '{').is_some() {
+                    depth += 1;
+                } else {
+                    chars.next_if(|(_, char)| *char == '$');
+                }
+            }
+            '}' if depth == 0 => {
+                end = Some(index);
+                break;
+            }
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    let end =
+        end.ok_or_else(|| eyre!("variable reference `${{{input}` is missing a closing `}}`"))?;
+
+    let (word, rest) = rest.split_at(end);
+    let rest = rest.strip_prefix('}').unwrap_or(rest);
+
+    Ok((
+        Variable {
+            name,
+            modifier: Some(Modifier { kind, empty, word }),
+        },
+        rest,
+    ))
+}
+
+/// Split a variable name from the start of `input`.
+///
+/// Variable names consist of ASCII letters, digits, and underscores (_), and must not start with a
+/// digit. If `input` does not start with a valid name, the returned name is empty.
+fn split_name(input: &str) -> (&str, &str) {
+    if input.starts_with(|char: char| char.is_ascii_digit()) {
+        return ("", input);
+    }
+    let end = input
+        .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_'))
+        .unwrap_or(input.len());
+    input.split_at(end)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Variables {
+        let mut variables = Variables::default();
+        variables
+            .parse_env_file(
+                "# comment\n\
+                PODLET_TEST_ONE=1\n\
+                export PODLET_TEST_TWO = 'two $PODLET_TEST_ONE' # comment\n\
+                PODLET_TEST_THREE=\"three\\t${PODLET_TEST_ONE}\"\n\
+                PODLET_TEST_EMPTY=\n\
+                PODLET_TEST_FOUR=four #comment\n",
+            )
+            .unwrap();
+        variables
+    }
+
+    #[test]
+    fn env_file() {
+        let variables = variables();
+        let expected: HashMap<String, String> = [
+            ("PODLET_TEST_ONE", "1"),
+            ("PODLET_TEST_TWO", "two $PODLET_TEST_ONE"),
+            ("PODLET_TEST_THREE", "three\t1"),
+            ("PODLET_TEST_EMPTY", ""),
+            ("PODLET_TEST_FOUR", "four"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .collect();
+        assert_eq!(variables.values, expected);
+    }
+
+    #[test]
+    fn substitute_modifiers() {
+        let variables = variables();
+        let mut unset = IndexSet::new();
+        let mut substitute = |input| substitute(input, &variables, &mut unset).unwrap();
+
+        assert_eq!(substitute("$PODLET_TEST_ONE-${PODLET_TEST_FOUR}"), "1-four");
+        assert_eq!(substitute("$$PODLET_TEST_ONE"), "$PODLET_TEST_ONE");
+        assert_eq!(substitute("${PODLET_TEST_EMPTY:-default}"), "default");
+        assert_eq!(substitute("${PODLET_TEST_EMPTY-default}"), "");
+        assert_eq!(substitute("${PODLET_TEST_UNSET:-${PODLET_TEST_ONE}}"), "1");
+        assert_eq!(substitute("${PODLET_TEST_ONE:+set}"), "set");
+        assert_eq!(substitute("${PODLET_TEST_UNSET+set}"), "");
+        assert_eq!(substitute("${PODLET_TEST_ONE:?error}"), "1");
+        assert_eq!(substitute("$PODLET_TEST_UNSET"), "");
+        assert!(unset.contains("PODLET_TEST_UNSET"));
+    }
+
+    #[test]
+    fn substitute_required() {
+        let variables = variables();
+        let mut unset = IndexSet::new();
+
+        let error =
+            substitute("${PODLET_TEST_UNSET:?must be set}", &variables, &mut unset).unwrap_err();
+        assert_eq!(error.to_string(), "must be set");
+
+        assert!(substitute("${PODLET_TEST_EMPTY:?}", &variables, &mut unset).is_err());
+        assert!(substitute("${PODLET_TEST_EMPTY?}", &variables, &mut unset).is_ok());
+    }
+
+    #[test]
+    fn invalid_references() {
+        assert!(tokenize("${}").is_err());
+        assert!(tokenize("${NAME").is_err());
+        assert!(tokenize("${NAME:-default").is_err());
+        assert!(tokenize("${NAME:=default}").is_err());
+        assert_eq!(
+            tokenize("$1 $").unwrap(),
+            [
+                Token::Literal("$"),
+                Token::Literal("1 "),
+                Token::Literal("$")
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_references() {
+        let mut kept = IndexMap::new();
+        assert_eq!(
+            keep("$ONE ${TWO:-2} ${THREE:?error} $$", &mut kept).unwrap(),
+            "${ONE} ${TWO} ${THREE} $$"
+        );
+        assert_eq!(
+            kept,
+            [
+                (String::from("ONE"), None),
+                (String::from("TWO"), Some(String::from("2"))),
+                (String::from("THREE"), None),
+            ]
+            .into_iter()
+            .collect::<IndexMap<_, _>>()
+        );
+
+        assert!(keep("${TWO:-3}", &mut kept).is_err());
+        assert!(keep("${FOUR:+alternative}", &mut kept).is_err());
+    }
+
+    #[test]
+    fn interpolate_value() {
+        let mut interpolator = Interpolator::new(variables(), false);
+        let mut value: Value = serde_yaml::from_str(
+            "services:\n  \
+                test:\n    \
+                    image: image:${PODLET_TEST_FOUR}\n    \
+                    scale: ${PODLET_TEST_ONE}\n",
+        )
+        .unwrap();
+
+        interpolator.interpolate(&mut value).unwrap();
+
+        assert_eq!(
+            value,
+            serde_yaml::from_str::<Value>(
+                "services:\n  \
+                    test:\n    \
+                        image: image:four\n    \
+                        scale: 1\n",
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn interpolate_numeric_strings() {
+        let mut variables = Variables::default();
+        variables
+            .parse_env_file(
+                "PODLET_TEST_NAME=123\n\
+                PODLET_TEST_VERSION=1.10\n\
+                PODLET_TEST_TRUE=true\n\
+                PODLET_TEST_MODE=0440\n\
+                PODLET_TEST_DEPLOY_MODE=1\n",
+            )
+            .unwrap();
+        let mut interpolator = Interpolator::new(variables, false);
+        let mut value: Value = serde_yaml::from_str(
+            "services:\n  \
+                test:\n    \
+                    container_name: ${PODLET_TEST_NAME}\n    \
+                    environment:\n      \
+                        VERSION: ${PODLET_TEST_VERSION}\n      \
+                        tty: ${PODLET_TEST_TRUE}\n    \
+                    labels:\n      \
+                        version: ${PODLET_TEST_VERSION}\n    \
+                    tty: ${PODLET_TEST_TRUE}\n    \
+                    ulimits:\n      \
+                        nofile: ${PODLET_TEST_NAME}\n    \
+                    configs:\n      \
+                        - source: app\n        \
+                          mode: ${PODLET_TEST_MODE}\n    \
+                    deploy:\n      \
+                        mode: ${PODLET_TEST_DEPLOY_MODE}\n",
+        )
+        .unwrap();
+
+        interpolator.interpolate(&mut value).unwrap();
+
+        assert_eq!(
+            value,
+            serde_yaml::from_str::<Value>(
+                "services:\n  \
+                    test:\n    \
+                        container_name: '123'\n    \
+                        environment:\n      \
+                            VERSION: '1.10'\n      \
+                            tty: 'true'\n    \
+                        labels:\n      \
+                            version: '1.10'\n    \
+                        tty: true\n    \
+                        ulimits:\n      \
+                            nofile: 123\n    \
+                        configs:\n      \
+                            - source: app\n        \
+                              mode: 0o440\n    \
+                        deploy:\n      \
+                            mode: '1'\n",
+            )
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Conversion of the legacy compose service `links` and `external_links` fields.
+
+use std::mem;
+
+use color_eyre::{
+    eyre::{eyre, OptionExt, WrapErr},
+    Section,
+};
+use compose_spec::{
+    service::{network_config::NetworkConfig, Hostname, Link},
+    Identifier, Networks, Service,
+};
+use indexmap::{IndexMap, IndexSet};
+
+/// Name of the network services are on if they do not set `networks` or `network_mode`.
+const DEFAULT_NETWORK: &str = "default";
+
+/// Convert the `links` of compose `services` into network aliases and dependencies.
+///
+/// For each link, the alias (or the linked service's name if there is none) is added to the
+/// `aliases` of the networks the linking and linked services share, and the linked service is added
+/// to the linking service's `depends_on`.
+///
+/// Services without `networks` or a `network_mode` are on the implicit `default` network. If a link
+/// uses it, those services are explicitly added to the `default` network, which is added to the
+/// compose `networks` if not already present, so that the alias can be added to it.
+///
+/// `external_links` without an alias are dropped, as containers on the same network can already
+/// reach each other by name.
+///
+/// # Errors
+///
+/// Returns an error if a service links to a service which does not exist, the services do not share
+/// a network, an alias is not a valid hostname, or an external link has an alias.
+pub fn into_aliases(
+    services: &mut IndexMap<Identifier, Service>,
+    networks: &mut Networks,
+) -> color_eyre::Result<()> {
+    let links: Vec<(Identifier, IndexSet<Link>)> = services
+        .iter_mut()
+        .filter(|(_, service)| !service.links.is_empty())
+        .map(|(name, service)| (name.clone(), mem::take(&mut service.links)))
+        .collect();
+
+    let uses_default = links.iter().any(|(name, links)| {
+        [name]
+            .into_iter()
+            .chain(links.iter().map(|link| &link.service))
+            .any(|name| {
+                services
+                    .get(name)
+                    .is_some_and(|service| service.network_config.is_none())
+            })
+    });
+    if uses_default {
+        add_default_network(services, networks);
+    }
+
+    for (name, links) in links {
+        for Link { service, alias } in links {
+            add_alias(services, &name, &service, alias).wrap_err_with(|| {
+                format!("error converting link from service `{name}` to `{service}`")
+            })?;
+
+            let depends_on = &mut services
+                .get_mut(&name)
+                .ok_or_eyre("linking service does not exist")?
+                .depends_on;
+            let mut dependencies = mem::take(depends_on).into_long();
+            dependencies.entry(service).or_default();
+            *depends_on = dependencies.into();
+        }
+    }
+
+    for (name, service) in services {
+        for Link { service, alias } in mem::take(&mut service.external_links) {
+            if let Some(alias) = alias {
+                return Err(eyre!(
+                    "service `{name}` uses alias `{alias}` for external link `{service}`, \
+                        aliases of external links are not supported"
+                )
+                .suggestion(
+                    "add the alias to the external container's network, \
+                        or refer to the container by its name",
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Explicitly add `services` on the implicit `default` network to it, and add the `default`
+/// network to `networks` if not already present.
+fn add_default_network(services: &mut IndexMap<Identifier, Service>, networks: &mut Networks) {
+    let default = Identifier::new(DEFAULT_NETWORK).expect("`default` is a valid identifier");
+
+    for service in services.values_mut() {
+        if service.network_config.is_none() {
+            service.network_config = Some(NetworkConfig::Networks(
+                IndexSet::from([default.clone()]).into(),
+            ));
+        }
+    }
+
+    networks.entry(default).or_insert(None);
+}
+
+/// Add `alias`, or the `linked` service's name if [`None`], to the network aliases of the `linked`
+/// service for each network it shares with the `linking` service.
+///
+/// # Errors
+///
+/// Returns an error if either service does not exist, the services do not share a network, or the
+/// alias is not a valid hostname.
+fn add_alias(
+    services: &mut IndexMap<Identifier, Service>,
+    linking: &Identifier,
+    linked: &Identifier,
+    alias: Option<String>,
+) -> color_eyre::Result<()> {
+    let linking_networks: IndexSet<Identifier> = match &services
+        .get(linking)
+        .ok_or_eyre("linking service does not exist")?
+        .network_config
+    {
+        Some(NetworkConfig::Networks(networks)) => {
+            networks.clone().into_long().into_keys().collect()
+        }
+        _ => IndexSet::new(),
+    };
+
+    let alias = Hostname::new(alias.unwrap_or_else(|| linked.to_string()))
+        .wrap_err("link alias is not a valid hostname")?;
+
+    let Some(NetworkConfig::Networks(networks)) = &mut services
+        .get_mut(linked)
+        .ok_or_eyre("linked service does not exist")?
+        .network_config
+    else {
+        return Err(not_shared());
+    };
+
+    let mut long = mem::take(networks).into_long();
+    let mut shared = false;
+    for (network, options) in &mut long {
+        if linking_networks.contains(network) {
+            options
+                .get_or_insert_with(Default::default)
+                .aliases
+                .insert(alias.clone());
+            shared = true;
+        }
+    }
+    *networks = long.into();
+
+    if shared {
+        Ok(())
+    } else {
+        Err(not_shared())
+    }
+}
+
+/// Error for linked services which do not share a network.
+fn not_shared() -> color_eyre::Report {
+    eyre!("the services do not share a network").suggestion(
+        "add both services to the same network, \
+            link aliases are added to the linked service on the networks they share",
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases() {
+        let mut services: IndexMap<Identifier, Service> = serde_yaml::from_str(
+            "web:\n  \
+                image: web\n  \
+                networks: [front, back]\n  \
+                links: ['db:database', cache]\n\
+            db:\n  \
+                image: db\n  \
+                networks: [back, other]\n\
+            cache:\n  \
+                image: cache\n  \
+                networks:\n    \
+                    back:\n      \
+                        aliases: [redis]\n",
+        )
+        .unwrap();
+        into_aliases(&mut services, &mut Networks::new()).unwrap();
+
+        let aliases = |service: &str, network: &str| -> Vec<String> {
+            let networks = match &services.get(service).unwrap().network_config {
+                Some(NetworkConfig::Networks(networks)) => Some(networks.clone().into_long()),
+                _ => None,
+            };
+            networks
+                .unwrap()
+                .swap_remove(network)
+                .unwrap()
+                .map(|network| network.aliases.iter().map(ToString::to_string).collect())
+                .unwrap_or_default()
+        };
+        assert_eq!(aliases("db", "back"), ["database"]);
+        assert!(aliases("db", "other").is_empty());
+        assert_eq!(aliases("cache", "back"), ["redis", "cache"]);
+
+        let web = services.get("web").unwrap();
+        assert!(web.links.is_empty());
+        let dependencies: Vec<_> = web
+            .depends_on
+            .clone()
+            .into_long()
+            .into_keys()
+            .map(String::from)
+            .collect();
+        assert_eq!(dependencies, ["db", "cache"]);
+    }
+
+    #[test]
+    fn default_network() {
+        let mut services: IndexMap<Identifier, Service> = serde_yaml::from_str(
+            "web:\n  \
+                image: web\n  \
+                links: [db]\n\
+            db:\n  \
+                image: db\n\
+            cache:\n  \
+                image: cache\n\
+            vpn:\n  \
+                image: vpn\n  \
+                network_mode: host\n",
+        )
+        .unwrap();
+        let mut networks = Networks::new();
+        into_aliases(&mut services, &mut networks).unwrap();
+
+        let networks: Vec<_> = networks.keys().map(Identifier::as_str).collect();
+        assert_eq!(networks, [DEFAULT_NETWORK]);
+
+        let db = match &services.get("db").unwrap().network_config {
+            Some(NetworkConfig::Networks(networks)) => Some(networks.clone().into_long()),
+            _ => None,
+        };
+        let aliases: Vec<String> = db
+            .unwrap()
+            .swap_remove(DEFAULT_NETWORK)
+            .unwrap()
+            .unwrap()
+            .aliases
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(aliases, ["db"]);
+
+        let default = Some(NetworkConfig::Networks(
+            IndexSet::from([Identifier::new(DEFAULT_NETWORK).unwrap()]).into(),
+        ));
+        assert_eq!(services.get("web").unwrap().network_config, default);
+        assert_eq!(services.get("cache").unwrap().network_config, default);
+        assert_ne!(services.get("vpn").unwrap().network_config, default);
+    }
+
+    #[test]
+    fn not_shared_network() {
+        let mut services: IndexMap<Identifier, Service> = serde_yaml::from_str(
+            "web:\n  \
+                image: web\n  \
+                networks: [front]\n  \
+                links: [db]\n\
+            db:\n  \
+                image: db\n  \
+                networks: [back]\n",
+        )
+        .unwrap();
+        assert!(into_aliases(&mut services, &mut Networks::new()).is_err());
+    }
+
+    #[test]
+    fn external_alias() {
+        let mut services: IndexMap<Identifier, Service> = serde_yaml::from_str(
+            "web:\n  \
+                image: web\n  \
+                external_links: [redis]\n",
+        )
+        .unwrap();
+        into_aliases(&mut services, &mut Networks::new()).unwrap();
+
+        services.get_mut("web").unwrap().external_links =
+            IndexSet::from([Link::parse("redis:cache").unwrap()]);
+        assert!(into_aliases(&mut services, &mut Networks::new()).is_err());
+    }
+}
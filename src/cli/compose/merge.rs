@@ -0,0 +1,420 @@
+//! [Merging](https://github.com/compose-spec/compose-spec/blob/master/13-merge.md) of multiple
+//! compose files.
+
+use serde_yaml::{Mapping, Value};
+
+/// Merge the compose file `other` into `base` following the compose spec's merge rules.
+///
+/// - Mappings are merged recursively, with values from `other` taking precedence.
+/// - Shell commands (`command`, `entrypoint`, and `healthcheck.test`) are replaced.
+/// - Fields which may be a list or a mapping, like `environment` and `labels`, are normalized to
+///   mappings and merged.
+/// - Unique resources (`ports`, `volumes`, `secrets`, `configs`, and `devices`) are merged by
+///   their target, with entries from `other` taking precedence.
+/// - Sequences of unique values, like `dns` and `cap_add`, are merged without duplicates.
+/// - Other sequences are appended.
+/// - Values tagged with `!reset` are removed and values tagged with `!override` replace the
+///   value in `base` without merging.
+pub fn merge(base: &mut Value, other: Value) {
+    merge_value(base, other, &[]);
+}
+
+/// Merge the compose service `other` into `base` following the compose spec's merge rules.
+///
+/// See [`merge()`] for the rules used.
+pub fn merge_service(base: &mut Value, other: Value) {
+    merge_value(base, other, &["services", "*"]);
+}
+
+/// Remove the `!reset` and `!override` tags from a compose file.
+///
+/// Values tagged with `!reset` are removed, and `!override` tags are replaced by their value.
+pub fn strip_tags(value: &mut Value) {
+    match value {
+        Value::Mapping(mapping) => {
+            mapping.retain(|_, value| !is_reset(value));
+            for value in mapping.values_mut() {
+                strip_tags(value);
+            }
+        }
+        Value::Sequence(sequence) => {
+            sequence.retain(|value| !is_reset(value));
+            for value in sequence {
+                strip_tags(value);
+            }
+        }
+        Value::Tagged(tagged) if tagged.tag == "override" => {
+            *value = tagged.value.clone();
+            strip_tags(value);
+        }
+        Value::Tagged(tagged) => strip_tags(&mut tagged.value),
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+    }
+}
+
+/// Returns `true` if the `value` is tagged with `!reset`.
+fn is_reset(value: &Value) -> bool {
+    matches!(value, Value::Tagged(tagged) if tagged.tag == "reset")
+}
+
+/// How a field is merged, based on its path within the compose file.
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// Mappings are merged recursively, sequences are appended, and other values are replaced.
+    Default,
+    /// The value is replaced.
+    Replace,
+    /// A list or mapping, normalized to a mapping with list items split on the separator.
+    Map(char),
+    /// A list of names or a mapping of names to options, normalized to a mapping.
+    NameMap,
+    /// A sequence of unique resources, identified with the key function.
+    Unique(fn(&Value) -> Option<String>),
+    /// A sequence of unique values.
+    Union,
+}
+
+impl Rule {
+    /// Determine the merge rule for the field at `path`.
+    ///
+    /// The names of services and top-level resources are replaced with `*` in the path.
+    fn from_path(path: &[&str]) -> Self {
+        match path {
+            ["services", "*", field] => match *field {
+                "command" | "entrypoint" => Self::Replace,
+                "environment" | "labels" | "annotations" | "sysctls" => Self::Map('='),
+                "networks" | "depends_on" => Self::NameMap,
+                "ports" => Self::Unique(port_key),
+                "volumes" => Self::Unique(volume_key),
+                "secrets" | "configs" => Self::Unique(file_key),
+                "devices" => Self::Unique(device_key),
+                "cap_add"
+                | "cap_drop"
+                | "device_cgroup_rules"
+                | "dns"
+                | "dns_opt"
+                | "dns_search"
+                | "env_file"
+                | "expose"
+                | "external_links"
+                | "extra_hosts"
+                | "group_add"
+                | "links"
+                | "profiles"
+                | "security_opt"
+                | "tmpfs"
+                | "volumes_from" => Self::Union,
+                _ => Self::Default,
+            },
+            ["services", "*", "healthcheck", "test"] => Self::Replace,
+            ["services", "*", "build" | "deploy", "labels"]
+            | ["services", "*", "build", "args"]
+            | ["networks" | "volumes" | "configs" | "secrets", "*", "labels"] => Self::Map('='),
+            _ => Self::Default,
+        }
+    }
+}
+
+/// Merge `other` into `base`, where both are at `path` within the compose file.
+fn merge_value(base: &mut Value, other: Value, path: &[&str]) {
+    let other = match other {
+        Value::Tagged(tagged) if tagged.tag == "override" => {
+            *base = tagged.value;
+            return;
+        }
+        other => other,
+    };
+
+    match (Rule::from_path(path), base, other) {
+        (Rule::Replace, base, other) => *base = other,
+        (Rule::Map(separator), base, other) => {
+            let mut mapping = normalize_map(base, separator);
+            for (key, value) in normalize_map(&other, separator) {
+                mapping.insert(key, value);
+            }
+            *base = mapping.into();
+        }
+        (Rule::NameMap, base, other) => {
+            let mut mapping = normalize_name_map(base, path);
+            for (key, value) in normalize_name_map(&other, path) {
+                if let Some(base) = mapping.get_mut(&key) {
+                    let mut path = path.to_vec();
+                    path.push("*");
+                    merge_value(base, value, &path);
+                } else {
+                    mapping.insert(key, value);
+                }
+            }
+            *base = mapping.into();
+        }
+        (Rule::Unique(key), Value::Sequence(base), Value::Sequence(other)) => {
+            for value in other {
+                let value_key = key(&value);
+                if let Some(existing) = base
+                    .iter_mut()
+                    .find(|existing| value_key.is_some() && key(existing) == value_key)
+                {
+                    *existing = value;
+                } else {
+                    base.push(value);
+                }
+            }
+        }
+        (Rule::Union, Value::Sequence(base), Value::Sequence(other)) => {
+            for value in other {
+                if !base.contains(&value) {
+                    base.push(value);
+                }
+            }
+        }
+        (_, Value::Mapping(base), Value::Mapping(other)) => merge_mapping(base, other, path),
+        (_, Value::Sequence(base), Value::Sequence(other)) => base.extend(other),
+        (_, base, other) => *base = other,
+    }
+}
+
+/// Merge the `other` [`Mapping`] into `base`, where both are at `path` within the compose file.
+fn merge_mapping(base: &mut Mapping, other: Mapping, path: &[&str]) {
+    for (key, value) in other {
+        if is_reset(&value) {
+            base.remove(&key);
+            continue;
+        }
+
+        let Some(base) = base.get_mut(&key) else {
+            base.insert(key, value);
+            continue;
+        };
+
+        // The names of services and top-level resources are not part of the path used for
+        // determining merge rules.
+        let segment = match (path, key.as_str()) {
+            (["services" | "networks" | "volumes" | "configs" | "secrets"], _) | (_, None) => "*",
+            (_, Some(key)) => key,
+        };
+        let mut path = path.to_vec();
+        path.push(segment);
+
+        merge_value(base, value, &path);
+    }
+}
+
+/// Normalize a list of `KEY{separator}VALUE` items, or a mapping, into a [`Mapping`].
+///
+/// List items without the separator are given a null value.
+fn normalize_map(value: &Value, separator: char) -> Mapping {
+    match value {
+        Value::Mapping(mapping) => mapping.clone(),
+        Value::Sequence(sequence) => sequence
+            .iter()
+            .filter_map(|item| {
+                let item = item.as_str()?;
+                Some(item.split_once(separator).map_or_else(
+                    || (item.into(), Value::Null),
+                    |(key, value)| (key.into(), value.into()),
+                ))
+            })
+            .collect(),
+        _ => Mapping::new(),
+    }
+}
+
+/// Normalize a list of names, or a mapping of names to options, into a [`Mapping`].
+///
+/// Names in a `depends_on` list are given the default condition, others are given a null value.
+fn normalize_name_map(value: &Value, path: &[&str]) -> Mapping {
+    match value {
+        Value::Mapping(mapping) => mapping.clone(),
+        Value::Sequence(sequence) => sequence
+            .iter()
+            .map(|name| {
+                let value = if path.last() == Some(&"depends_on") {
+                    Mapping::from_iter([("condition".into(), "service_started".into())]).into()
+                } else {
+                    Value::Null
+                };
+                (name.clone(), value)
+            })
+            .collect(),
+        _ => Mapping::new(),
+    }
+}
+
+/// Key identifying a port, from its host IP, published port, target port, and protocol.
+// Signature required by `Rule::Unique`.
+#[allow(clippy::unnecessary_wraps)]
+fn port_key(port: &Value) -> Option<String> {
+    match port {
+        Value::Mapping(port) => {
+            let field = |field| port.get(field).map(value_to_string).unwrap_or_default();
+            Some(format!(
+                "{}:{}:{}/{}",
+                field("host_ip"),
+                field("published"),
+                field("target"),
+                port.get("protocol")
+                    .map_or_else(|| "tcp".into(), value_to_string),
+            ))
+        }
+        port => {
+            let port = value_to_string(port);
+            let (port, protocol) = port.split_once('/').unwrap_or((&port, "tcp"));
+            // Host IPs may be IPv6 addresses, which contain colons.
+            let (host, target) = port.rsplit_once(':').unwrap_or(("", port));
+            let (host_ip, published) = host.rsplit_once(':').unwrap_or(("", host));
+            Some(format!("{host_ip}:{published}:{target}/{protocol}"))
+        }
+    }
+}
+
+/// Key identifying a volume, from its container target path.
+fn volume_key(volume: &Value) -> Option<String> {
+    match volume {
+        Value::Mapping(volume) => volume.get("target").map(value_to_string),
+        Value::String(volume) => {
+            let mut parts = volume.split(':');
+            let source = parts.next()?;
+            Some(parts.next().unwrap_or(source).to_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Key identifying a secret or config, from its target, or its source if no target is set.
+fn file_key(file: &Value) -> Option<String> {
+    match file {
+        Value::Mapping(file) => file
+            .get("target")
+            .or_else(|| file.get("source"))
+            .map(value_to_string),
+        Value::String(source) => Some(source.clone()),
+        _ => None,
+    }
+}
+
+/// Key identifying a device, from its container path.
+fn device_key(device: &Value) -> Option<String> {
+    match device {
+        Value::Mapping(device) => device
+            .get("target")
+            .or_else(|| device.get("source"))
+            .map(value_to_string),
+        Value::String(device) => {
+            let mut parts = device.split(':');
+            let source = parts.next()?;
+            Some(parts.next().unwrap_or(source).to_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Convert a scalar YAML [`Value`] into a [`String`].
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Number(number) => number.to_string(),
+        Value::Bool(bool) => bool.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn merge_str(base: &str, other: &str) -> Value {
+        let mut base = serde_yaml::from_str(base).unwrap();
+        merge(&mut base, serde_yaml::from_str(other).unwrap());
+        strip_tags(&mut base);
+        base
+    }
+
+    #[test]
+    fn services() {
+        let merged = merge_str(
+            "services:\n  \
+                web:\n    \
+                    image: image\n    \
+                    command: [one, two]\n    \
+                    environment:\n      \
+                        - ONE=1\n      \
+                        - TWO=2\n    \
+                    ports:\n      \
+                        - 8080:80\n      \
+                        - 127.0.0.1:9090:90\n    \
+                    volumes:\n      \
+                        - data:/data\n    \
+                    dns: [1.1.1.1]\n    \
+                    networks: [default]\n",
+            "services:\n  \
+                web:\n    \
+                    image: other\n    \
+                    command: [three]\n    \
+                    environment:\n      \
+                        TWO: two\n    \
+                    ports:\n      \
+                        - 8081:80\n      \
+                        - 127.0.0.1:9090:90\n    \
+                    volumes:\n      \
+                        - type: bind\n        \
+                          source: ./data\n        \
+                          target: /data\n    \
+                    dns: [1.1.1.1, 8.8.8.8]\n    \
+                    networks:\n      \
+                        other:\n",
+        );
+
+        let expected: Value = serde_yaml::from_str(
+            "services:\n  \
+                web:\n    \
+                    image: other\n    \
+                    command: [three]\n    \
+                    environment:\n      \
+                        ONE: '1'\n      \
+                        TWO: two\n    \
+                    ports:\n      \
+                        - 8080:80\n      \
+                        - 127.0.0.1:9090:90\n      \
+                        - 8081:80\n    \
+                    volumes:\n      \
+                        - type: bind\n        \
+                          source: ./data\n        \
+                          target: /data\n    \
+                    dns: [1.1.1.1, 8.8.8.8]\n    \
+                    networks:\n      \
+                        default:\n      \
+                        other:\n",
+        )
+        .unwrap();
+
+        assert_eq!(merged, expected);
+    }
+
+    #[test]
+    fn tags() {
+        let merged = merge_str(
+            "services:\n  \
+                web:\n    \
+                    image: image\n    \
+                    ports: [8080:80]\n    \
+                    build: .\n    \
+                    labels: [one=1]\n",
+            "services:\n  \
+                web:\n    \
+                    ports: !override [9090:80]\n    \
+                    build: !reset null\n    \
+                    labels: !reset {}\n",
+        );
+
+        let expected: Value = serde_yaml::from_str(
+            "services:\n  \
+                web:\n    \
+                    image: image\n    \
+                    ports: [9090:80]\n",
+        )
+        .unwrap();
+
+        assert_eq!(merged, expected);
+    }
+}
@@ -0,0 +1,169 @@
+//! Support for compose [`secrets`](https://github.com/compose-spec/compose-spec/blob/master/09-secrets.md)
+//! which are not external.
+//!
+//! Podman secrets are created from the secret's file or environment variable with
+//! `podman secret create --replace` in an `ExecStartPre=` command of each container which uses
+//! the secret.
+
+use std::{env, path::Path};
+
+use color_eyre::eyre::{bail, ensure, WrapErr};
+use compose_spec::{
+    secret::Source, service::ConfigOrSecret, Identifier, Resource, Secret, ShortOrLong,
+};
+use indexmap::IndexMap;
+
+use crate::{cli::Service, escape::command_join};
+
+/// Resolved top-level compose [`Secrets`](compose_spec::Secrets) which need to be created.
+///
+/// External secrets are not included as they already exist.
+#[derive(Debug, Default)]
+pub struct Secrets {
+    /// Arguments for `podman secret create` for each secret, by their identifier.
+    create_args: IndexMap<Identifier, Vec<String>>,
+}
+
+impl Secrets {
+    /// Resolve top-level compose `secrets`.
+    ///
+    /// Relative file paths are resolved from the `project_directory`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the current directory could not be determined or a secret uses an
+    /// unsupported option.
+    pub fn resolve(
+        secrets: compose_spec::Secrets,
+        project_directory: &Path,
+    ) -> color_eyre::Result<Self> {
+        let project_directory = env::current_dir()
+            .wrap_err("error getting current working directory")?
+            .join(project_directory);
+
+        let create_args = secrets
+            .into_iter()
+            .filter_map(|(id, secret)| match secret {
+                Resource::External { .. } => None,
+                Resource::Compose(secret) => Some(
+                    secret_try_into_create_args(&id, secret, &project_directory)
+                        .wrap_err_with(|| format!("error converting secret `{id}`"))
+                        .map(|args| (id, args)),
+                ),
+            })
+            .collect::<color_eyre::Result<_>>()?;
+
+        Ok(Self { create_args })
+    }
+
+    /// Add an `ExecStartPre=` command to the `service` which creates each of the service's
+    /// `secrets`.
+    ///
+    /// Secrets which are not in the top-level `secrets` or are external are skipped.
+    pub fn add_to_service(
+        &self,
+        secrets: &[ShortOrLong<Identifier, ConfigOrSecret>],
+        service: &mut Service,
+    ) {
+        for secret in secrets {
+            let source = match secret {
+                ShortOrLong::Short(source) | ShortOrLong::Long(ConfigOrSecret { source, .. }) => {
+                    source
+                }
+            };
+            if let Some(args) = self.create_args.get(source) {
+                service.add_exec_start_pre(command_join(args));
+            }
+        }
+    }
+}
+
+/// Attempt to convert a compose [`Secret`] into arguments for `podman secret create`.
+///
+/// The Podman secret is named `id`, the same as the compose secret, so it can be referenced by
+/// `Secret=` in the Quadlet file.
+///
+/// # Errors
+///
+/// Returns an error if the secret has a `driver`, `driver_opts`, or extensions, or its file path is
+/// not valid UTF-8.
+fn secret_try_into_create_args(
+    id: &Identifier,
+    Secret {
+        source,
+        labels,
+        driver,
+        driver_opts,
+        extensions,
+    }: Secret,
+    project_directory: &Path,
+) -> color_eyre::Result<Vec<String>> {
+    ensure!(driver.is_none(), "`driver` is not supported");
+    ensure!(driver_opts.is_empty(), "`driver_opts` is not supported");
+    ensure!(
+        extensions.is_empty(),
+        "compose extensions are not supported"
+    );
+
+    let mut args: Vec<String> = ["podman", "secret", "create", "--replace"]
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    for label in labels.into_list() {
+        args.push("--label".into());
+        args.push(label);
+    }
+
+    match source {
+        Source::File(path) => {
+            let path = crate::cli::absolute_clean_path(project_directory, &path);
+            let Ok(path) = path.into_os_string().into_string() else {
+                bail!("`file` must only contain valid UTF-8");
+            };
+            args.extend([id.to_string(), path]);
+        }
+        Source::Environment(variable) => {
+            super::warn(format_args!(
+                "the `{variable}` environment variable must be set in the environment of the \
+                    services which use the `{id}` secret"
+            ));
+            args.extend(["--env".into(), id.to_string(), variable]);
+        }
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create() {
+        let secrets: compose_spec::Secrets = serde_yaml::from_str(
+            "file:\n  \
+                file: secret.txt\n  \
+                labels: [one=1]\n\
+            env:\n  \
+                environment: VAR\n\
+            external:\n  \
+                external: true\n",
+        )
+        .unwrap();
+        let secrets = Secrets::resolve(secrets, Path::new("/project")).unwrap();
+
+        let service_secrets =
+            serde_yaml::from_str::<Vec<_>>("[file, {source: env, target: env}, external]").unwrap();
+        let mut service = Service::default();
+        secrets.add_to_service(&service_secrets, &mut service);
+
+        assert_eq!(
+            service.to_string(),
+            "[Service]\n\
+            ExecStartPre=podman secret create --replace --label 'one=1' file /project/secret.txt\n\
+            ExecStartPre=podman secret create --replace --env env VAR\n"
+        );
+    }
+}
@@ -8,9 +8,14 @@ use color_eyre::eyre::{Context, OptionExt};
 
 use crate::escape::command_join;
 
+pub(super) use self::quadlet::ulimit_try_into_short;
+
 use self::{podman::PodmanArgs, quadlet::QuadletOptions, security_opt::SecurityOpt};
 
-use super::image_to_name;
+use super::{
+    image_to_name,
+    lenient::{ensure_empty_or_drop, UnsupportedField},
+};
 
 #[allow(clippy::doc_markdown)]
 #[derive(Args, Default, Debug, Clone, PartialEq)]
@@ -59,12 +64,19 @@ impl Container {
     pub(super) fn set_pod(&mut self, pod: Option<String>) {
         self.podman_args.set_pod(pod);
     }
-}
-
-impl TryFrom<compose_spec::Service> for Container {
-    type Error = color_eyre::Report;
 
-    fn try_from(value: compose_spec::Service) -> Result<Self, Self::Error> {
+    /// Attempt to convert a [`compose_spec::Service`] into a [`Container`].
+    ///
+    /// If `dropped` is [`Some`], unsupported fields are added to it instead of returning an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an unsupported field is set and `dropped` is [`None`], or a field could
+    /// not be converted.
+    pub fn try_from_compose(
+        value: compose_spec::Service,
+        dropped: Option<&mut Vec<UnsupportedField>>,
+    ) -> color_eyre::Result<Self> {
         let compose::Service {
             unsupported,
             quadlet,
@@ -77,7 +89,7 @@ impl TryFrom<compose_spec::Service> for Container {
                 },
         } = compose::Service::from(value);
 
-        unsupported.ensure_empty()?;
+        ensure_empty_or_drop(unsupported.fields(), dropped)?;
 
         let security_opt = security_opt
             .into_iter()
@@ -106,6 +118,14 @@ impl TryFrom<compose_spec::Service> for Container {
     }
 }
 
+impl TryFrom<compose_spec::Service> for Container {
+    type Error = color_eyre::Report;
+
+    fn try_from(value: compose_spec::Service) -> Result<Self, Self::Error> {
+        Self::try_from_compose(value, None)
+    }
+}
+
 impl From<Container> for crate::quadlet::Container {
     fn from(
         Container {
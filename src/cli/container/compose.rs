@@ -3,7 +3,6 @@
 
 use std::{net::IpAddr, time::Duration};
 
-use color_eyre::eyre::ensure;
 use compose_spec::{
     service::{
         build::Context, device::CgroupRule, AbsolutePath, BlkioConfig, Build, ByteValue, Cgroup,
@@ -16,6 +15,8 @@ use compose_spec::{
 };
 use indexmap::{IndexMap, IndexSet};
 
+use crate::cli::lenient::UnsupportedField;
+
 /// A struct for splitting up a [`compose_spec::Service`] into parts used to construct a
 /// [`Container`](super::Container).
 pub struct Service {
@@ -143,7 +144,6 @@ impl From<compose_spec::Service> for Service {
                 memswap_limit,
                 profiles,
                 scale,
-                volumes_from,
                 extensions,
             },
             quadlet: Quadlet {
@@ -209,6 +209,7 @@ impl From<compose_spec::Service> for Service {
                 stdin_open,
                 stop_signal,
                 tty,
+                volumes_from,
             },
             container: Container {
                 command,
@@ -238,17 +239,12 @@ pub struct Unsupported {
     memswap_limit: Option<Limit<ByteValue>>,
     profiles: IndexSet<Identifier>,
     scale: Option<u64>,
-    volumes_from: IndexSet<VolumesFrom>,
     extensions: Extensions,
 }
 
 impl Unsupported {
-    /// Ensure that all unsupported fields are [`None`] or empty.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if a field is not empty.
-    pub fn ensure_empty(&self) -> color_eyre::Result<()> {
+    /// Unsupported fields which are not [`None`] or empty.
+    pub fn fields(&self) -> Vec<UnsupportedField> {
         let Self {
             attach,
             build,
@@ -267,7 +263,6 @@ impl Unsupported {
             memswap_limit,
             profiles,
             scale,
-            volumes_from,
             extensions,
         } = self;
 
@@ -289,19 +284,24 @@ impl Unsupported {
             ("memswap_limit", memswap_limit.is_none()),
             ("profiles", profiles.is_empty()),
             ("scale", scale.is_none()),
-            ("volumes_from", volumes_from.is_empty()),
         ];
 
-        for (option, not_present) in unsupported_options {
-            ensure!(not_present, "`{option}` is not supported");
-        }
+        let mut fields: Vec<_> = unsupported_options
+            .into_iter()
+            .filter(|(_, not_present)| !not_present)
+            .map(|(option, _)| {
+                UnsupportedField::new(option, format!("`{option}` is not supported"))
+            })
+            .collect();
 
-        ensure!(
-            logging_extensions.is_empty() && extensions.is_empty(),
-            "compose extensions are not supported"
-        );
+        if !logging_extensions.is_empty() || !extensions.is_empty() {
+            fields.push(UnsupportedField::new(
+                "extensions",
+                "compose extensions are not supported",
+            ));
+        }
 
-        Ok(())
+        fields
     }
 }
 
@@ -372,6 +372,7 @@ pub struct PodmanArgs {
     pub stdin_open: bool,
     pub stop_signal: Option<String>,
     pub tty: bool,
+    pub volumes_from: IndexSet<VolumesFrom>,
 }
 
 /// Fields taken from a [`compose_spec::Service`] for constructing the top-level fields in
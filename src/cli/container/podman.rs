@@ -13,7 +13,7 @@ use color_eyre::{
 };
 use compose_spec::service::{
     blkio_config::{BpsLimit, IopsLimit, Weight, WeightDevice},
-    BlkioConfig, Ipc,
+    BlkioConfig, Ipc, VolumesFrom, VolumesFromSource,
 };
 use serde::Serialize;
 use smart_default::SmartDefault;
@@ -467,6 +467,7 @@ impl TryFrom<compose::PodmanArgs> for PodmanArgs {
             stdin_open,
             stop_signal,
             tty,
+            volumes_from,
         }: compose::PodmanArgs,
     ) -> Result<Self, Self::Error> {
         let BlkioConfig {
@@ -549,11 +550,39 @@ impl TryFrom<compose::PodmanArgs> for PodmanArgs {
                 .unwrap_or_default(),
             stop_signal,
             tty,
+            volumes_from: volumes_from
+                .into_iter()
+                .map(volumes_from_try_into_short)
+                .collect::<Result<_, _>>()?,
             ..Self::default()
         })
     }
 }
 
+/// Convert [`VolumesFrom`] from a [`compose_spec::Service`] into a [`String`] suitable for the
+/// `volumes_from` field of [`PodmanArgs`].
+///
+/// # Errors
+///
+/// Returns an error if the source is a service, it must first be resolved into the name of the
+/// service's container.
+fn volumes_from_try_into_short(
+    VolumesFrom { source, read_only }: VolumesFrom,
+) -> color_eyre::Result<String> {
+    match source {
+        VolumesFromSource::Container(container) => {
+            let mut volumes_from = String::from(container);
+            if read_only {
+                volumes_from.push_str(":ro");
+            }
+            Ok(volumes_from)
+        }
+        VolumesFromSource::Service(service) => Err(eyre!(
+            "`volumes_from` service `{service}` must be resolved into a container name"
+        )),
+    }
+}
+
 /// Convert a [`BpsLimit`] from a [`compose_spec::Service`]'s [`BlkioConfig`] into a [`String`]
 /// suitable for the `device_read_bps` or `device_write_bps` field of [`PodmanArgs`].
 fn bps_limit_into_short(BpsLimit { path, rate }: BpsLimit) -> String {
@@ -1044,7 +1044,7 @@ fn secret_try_into_short(
 /// # Errors
 ///
 /// Returns an error if the [`Ulimit`] has extensions.
-fn ulimit_try_into_short(
+pub(in crate::cli) fn ulimit_try_into_short(
     (resource, ulimit): (service::Resource, ShortOrLong<u64, Ulimit>),
 ) -> color_eyre::Result<String> {
     match ulimit {
@@ -3,7 +3,7 @@ use std::{mem, ops::Not, path::PathBuf};
 use clap::{Args, ValueEnum};
 use serde::Serialize;
 
-use crate::quadlet::Globals;
+use crate::quadlet::{ExtraOptions, Globals};
 
 /// Podman global options
 ///
@@ -182,6 +182,7 @@ impl From<GlobalArgs> for Globals {
         Self {
             containers_conf_module: value.module,
             global_args: (!global_args.is_empty()).then_some(global_args),
+            extra: ExtraOptions::default(),
         }
     }
 }
@@ -1,5 +1,7 @@
 use clap::Args;
 
+use crate::quadlet::ExtraOptions;
+
 #[allow(clippy::doc_markdown)]
 #[derive(Args, Debug, Clone, PartialEq)]
 pub struct Install {
@@ -41,6 +43,7 @@ impl From<Install> for crate::quadlet::Install {
                 value.wanted_by
             },
             required_by: value.required_by,
+            extra: ExtraOptions::default(),
         }
     }
 }
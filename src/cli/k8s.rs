@@ -1,21 +1,47 @@
-//! Kubernetes YAML [`File`] for converting a [`Compose`] file into a [`Pod`] and
-//! [`PersistentVolumeClaim`]s.
+//! Kubernetes YAML [`File`] for converting a [`Compose`] file into [`Pod`]s, [`Service`]s,
+//! [`PersistentVolumeClaim`]s, [`ConfigMap`]s, and a [`Secret`].
 
+mod dependencies;
+mod import;
 mod service;
 mod volume;
 
-use std::fmt::{self, Display, Formatter};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
 
-use color_eyre::eyre::{ensure, OptionExt, WrapErr};
-use compose_spec::{Compose, Resource};
+use clap::{Args, ValueEnum};
+use color_eyre::{
+    eyre::{bail, ensure, eyre, OptionExt, WrapErr},
+    Section,
+};
+use compose_spec::{
+    config, secret,
+    service::{network_config, NetworkConfig},
+    Compose, Config, Configs, Identifier, Networks, Resource, Secrets,
+};
+use indexmap::{IndexMap, IndexSet};
 use k8s_openapi::{
-    api::core::v1::{PersistentVolumeClaim, Pod, PodSpec},
-    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    api::core::v1::{
+        ConfigMap, PersistentVolumeClaim, Pod, PodSpec, Secret, Service, ServicePort, ServiceSpec,
+    },
+    apimachinery::pkg::{apis::meta::v1::ObjectMeta, util::intstr::IntOrString},
+    ByteString,
+};
+
+use super::{
+    compose::{Interpolator, Variables},
+    lenient::LenientReport,
 };
 
-use self::service::Service;
+pub use self::import::Import;
+use self::service::Objects;
 
-/// A Kubernetes YAML file representing a [`Pod`] and optional [`PersistentVolumeClaim`]s.
+/// A Kubernetes YAML file representing [`Pod`]s and optional [`Service`]s,
+/// [`PersistentVolumeClaim`]s, [`ConfigMap`]s, and [`Secret`].
 ///
 /// Created by converting from a [`Compose`] file.
 #[derive(Debug)]
@@ -23,35 +49,148 @@ pub struct File {
     /// The name of the file, without the extension.
     pub name: String,
 
-    /// The Kubernetes [`Pod`].
-    pub pod: Pod,
+    /// The Kubernetes [`Pod`]s.
+    ///
+    /// There is one pod for all compose services, or one for each service, depending on the
+    /// [`Layout`].
+    pub pods: Vec<Pod>,
+
+    /// Optional Kubernetes [`Service`]s.
+    ///
+    /// Needed for the published ports of each pod when using [`Layout::PerService`].
+    pub services: Vec<Service>,
 
     /// Optional Kubernetes [`PersistentVolumeClaim`]s.
     ///
     /// Needed if a [`compose_spec::Volume`] has additional options set.
     pub persistent_volume_claims: Vec<PersistentVolumeClaim>,
+
+    /// Optional Kubernetes [`ConfigMap`]s.
+    ///
+    /// Needed if the [`Compose`] file has `configs` or a service has an `env_file`.
+    pub config_maps: Vec<ConfigMap>,
+
+    /// Optional Kubernetes [`Secret`].
+    ///
+    /// Needed if the [`Compose`] file has `secrets`.
+    pub secret: Option<Secret>,
+
+    /// Networks the pods join, for the `Network=` option of the `.kube` Quadlet file which uses
+    /// this file.
+    ///
+    /// Networks are not a part of the Kubernetes YAML.
+    pub networks: Vec<String>,
 }
 
 impl TryFrom<Compose> for File {
     type Error = color_eyre::Report;
 
-    fn try_from(
+    fn try_from(value: Compose) -> Result<Self, Self::Error> {
+        let interpolator = Interpolator::new(Variables::default(), false);
+        Self::try_from_compose(
+            value,
+            Path::new(""),
+            &interpolator,
+            Options::default(),
+            None,
+        )
+    }
+}
+
+/// [`Args`] for converting a [`Compose`] file into a Kubernetes YAML [`File`].
+#[derive(Args, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// How compose services are laid out in Kubernetes pods when using --kube
+    ///
+    /// All pods are in the same Kubernetes YAML file and started by one `.kube` file.
+    #[arg(
+        long = "kube-layout",
+        value_enum,
+        value_name = "LAYOUT",
+        default_value_t,
+        requires = "kube"
+    )]
+    pub layout: Layout,
+
+    /// How compose bind mounts are converted when using --kube
+    ///
+    /// Bind mounts with the "pvc" mode do not depend on the host's files, so the Kubernetes YAML
+    /// is portable to other hosts.
+    #[arg(
+        long = "kube-bind-mode",
+        value_enum,
+        value_name = "MODE",
+        default_value_t,
+        requires = "kube"
+    )]
+    pub bind_mode: BindMode,
+
+    /// Add a readiness probe to each container with a healthcheck when using --kube
+    ///
+    /// The readiness probe runs the same command as the liveness probe created from the service's
+    /// `healthcheck`. A container is not considered ready until its healthcheck passes.
+    #[arg(long, requires = "kube")]
+    pub readiness_probe: bool,
+}
+
+/// How compose services are laid out in Kubernetes [`Pod`]s.
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// All services are containers in a single pod named after the project.
+    #[default]
+    Pod,
+
+    /// Each service is put in its own pod named "{project}-{service}", with a Kubernetes service
+    /// for its published ports.
+    PerService,
+}
+
+/// How compose bind mounts are converted into Kubernetes volumes.
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BindMode {
+    /// Bind mounts are `hostPath` volumes of the bind's source.
+    #[default]
+    HostPath,
+
+    /// Bind mounts of directories are persistent volume claims named after the bind's source.
+    ///
+    /// File binds are still `hostPath` volumes.
+    Pvc,
+}
+
+impl File {
+    /// Attempt to convert a [`Compose`] file into a Kubernetes YAML [`File`].
+    ///
+    /// Relative paths of secret files and services' `env_file`s are resolved from the
+    /// `project_directory`. `environment` secrets take their value from the `interpolator`'s
+    /// variables. If `report` is [`Some`], unsupported service fields are added to it instead of
+    /// returning an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an unsupported option was used, unless it is a service field and
+    /// `report` is [`Some`], or a part of the compose file could not be converted.
+    pub fn try_from_compose(
         Compose {
             version: _,
             name,
             include,
-            services,
+            mut services,
             networks,
             volumes,
             configs,
             secrets,
             extensions,
         }: Compose,
-    ) -> Result<Self, Self::Error> {
-        ensure!(include.is_empty(), "`include` is not supported");
-        ensure!(networks.is_empty(), "`networks` is not supported");
-        ensure!(configs.is_empty(), "`configs` is not supported");
-        ensure!(secrets.is_empty(), "`secrets` is not supported");
+        project_directory: &Path,
+        interpolator: &Interpolator,
+        options: Options,
+        mut report: Option<&mut LenientReport>,
+    ) -> color_eyre::Result<Self> {
+        ensure!(
+            include.is_empty(),
+            "`include` must be resolved before converting"
+        );
         ensure!(
             extensions.is_empty(),
             "compose extensions are not supported"
@@ -59,27 +198,74 @@ impl TryFrom<Compose> for File {
 
         let name = name.map(String::from).ok_or_eyre("`name` is required")?;
 
-        let spec =
-            services
-                .into_iter()
-                .try_fold(PodSpec::default(), |mut spec, (name, service)| {
-                    Service::from_compose(&name, service)
-                        .add_to_pod_spec(&mut spec)
-                        .wrap_err_with(|| {
-                            format!("error adding service `{name}` to Kubernetes pod spec")
-                        })
-                        .map(|()| spec)
-                })?;
+        let config_map = configs_try_into_config_map(configs, format!("{name}-configs"))?;
+        let secret = secrets_try_into_secret(
+            secrets,
+            project_directory,
+            interpolator,
+            format!("{name}-secrets"),
+        )?;
 
-        let pod = Pod {
-            metadata: ObjectMeta {
-                name: Some(name.clone()),
-                ..ObjectMeta::default()
-            },
-            spec: Some(spec),
-            status: None,
+        let mut objects = Objects {
+            pod_name: &name,
+            project_directory,
+            config_map: config_map
+                .as_ref()
+                .and_then(|config_map| config_map.metadata.name.as_deref())
+                .unwrap_or_default(),
+            secret: secret
+                .as_ref()
+                .and_then(|secret| secret.metadata.name.as_deref())
+                .unwrap_or_default(),
+            env_config_maps: Vec::new(),
+            bind_claims: HashMap::new(),
         };
 
+        let networks = take_networks(&mut services, &networks)?;
+
+        let init_containers =
+            dependencies::sort(&mut services).wrap_err("error converting `depends_on`")?;
+        if options.layout == Layout::PerService {
+            if let Some(init_container) = init_containers.first() {
+                return Err(eyre!(
+                    "service `{init_container}` must be an init container, as another service \
+                        depends on it completing successfully, which requires a shared pod"
+                )
+                .suggestion("use `--kube-layout pod`"));
+            }
+        }
+
+        let mut pods = IndexMap::new();
+        if options.layout == Layout::Pod {
+            pods.insert(name.clone(), Pod::default());
+        }
+        for (service_name, service) in services {
+            let pod_name = match options.layout {
+                Layout::Pod => name.clone(),
+                Layout::PerService => format!("{name}-{service_name}"),
+            };
+            let mut dropped = Vec::new();
+            service::Service::from_compose(&service_name, service)
+                .add_to_pod(
+                    pods.entry(pod_name).or_default(),
+                    &mut objects,
+                    options.bind_mode,
+                    init_containers.contains(&service_name),
+                    report.is_some().then_some(&mut dropped),
+                )
+                .wrap_err_with(|| {
+                    format!("error adding service `{service_name}` to Kubernetes pod")
+                })?;
+            if let Some(report) = report.as_deref_mut() {
+                report.add(&service_name, dropped);
+            }
+        }
+
+        let env_config_maps = objects.env_config_maps;
+        let config_maps = config_map.into_iter().chain(env_config_maps).collect();
+
+        let (pods, services) = finish_pods(pods, options);
+
         let persistent_volume_claims = volumes
             .into_iter()
             .filter_map(|(name, volume)| match volume {
@@ -94,25 +280,418 @@ impl TryFrom<Compose> for File {
 
         Ok(Self {
             name,
-            pod,
+            pods,
+            services,
             persistent_volume_claims,
+            config_maps,
+            secret,
+            networks,
+        })
+    }
+}
+
+/// Take the networks of compose `services` for the `Network=` option of a `.kube` Quadlet file.
+///
+/// Networks which are external or have a `name` are referenced by their name, other networks by
+/// their generated `.network` Quadlet file. As all pods played by a `.kube` file share its
+/// networks, a warning is printed for each service which is added to networks it is not on in the
+/// compose file.
+///
+/// # Errors
+///
+/// Returns an error if a service's network has options set or is not one of the top-level
+/// compose `networks`.
+fn take_networks(
+    services: &mut IndexMap<Identifier, compose_spec::Service>,
+    networks: &Networks,
+) -> color_eyre::Result<Vec<String>> {
+    let mut service_networks = Vec::new();
+    let mut shared: IndexSet<Identifier> = IndexSet::new();
+    for (name, service) in services {
+        let Some(NetworkConfig::Networks(networks)) = service.network_config.take() else {
+            continue;
+        };
+        let mut ids = IndexSet::new();
+        for (network, options) in networks.into_long() {
+            if options.is_some_and(|options| options != network_config::Network::default()) {
+                return Err(eyre!(
+                    "options for network `{network}` of service `{name}` are not supported"
+                )
+                .suggestion(
+                    "remove the network's options, \
+                        pods join networks through the `.kube` file's `Network=` option",
+                ));
+            }
+            ids.insert(network);
+        }
+        shared.extend(ids.iter().cloned());
+        service_networks.push((name, ids));
+    }
+
+    for (name, ids) in service_networks {
+        let added: Vec<String> = shared
+            .difference(&ids)
+            .map(|network| format!("`{network}`"))
+            .collect();
+        if !added.is_empty() {
+            super::compose::warn(format_args!(
+                "service `{name}` is also added to the {} network(s), \
+                    as all pods of the `.kube` file share its networks",
+                added.join(", ")
+            ));
+        }
+    }
+
+    shared
+        .into_iter()
+        .map(|id| match networks.get(&id) {
+            Some(Some(Resource::External { name })) => {
+                Ok(name.clone().unwrap_or_else(|| id.to_string()))
+            }
+            Some(Some(Resource::Compose(compose_spec::Network {
+                name: Some(name), ..
+            }))) => Ok(name.clone()),
+            Some(_) => Ok(format!("{id}.network")),
+            None => Err(eyre!(
+                "network `{id}` is not defined in the top-level `networks`"
+            )),
         })
+        .collect()
+}
+
+/// Name [`Pod`]s, by pod name, and create the Kubernetes [`Service`]s for their published ports.
+///
+/// Pods are only labeled, and services are only created, when using [`Layout::PerService`].
+fn finish_pods(pods: IndexMap<String, Pod>, options: Options) -> (Vec<Pod>, Vec<Service>) {
+    let mut services = Vec::new();
+    let mut finished = Vec::with_capacity(pods.len());
+    for (pod_name, mut pod) in pods {
+        let spec = pod.spec.get_or_insert_with(PodSpec::default);
+        if options.readiness_probe {
+            for container in &mut spec.containers {
+                container
+                    .readiness_probe
+                    .clone_from(&container.liveness_probe);
+            }
+        }
+
+        let labels = (options.layout == Layout::PerService)
+            .then(|| BTreeMap::from([("app".to_owned(), pod_name.clone())]));
+        if let Some(labels) = &labels {
+            services.extend(ports_service(&pod_name, spec, labels.clone()));
+        }
+
+        pod.metadata.name = Some(pod_name);
+        pod.metadata.labels = labels;
+        finished.push(pod);
     }
+
+    (finished, services)
+}
+
+/// Create a Kubernetes [`Service`] for the published ports of the containers in a pod's `spec`.
+///
+/// The service is named after the pod and selects it with its `labels`. Returns [`None`] if no
+/// ports are published.
+fn ports_service(
+    pod_name: &str,
+    spec: &PodSpec,
+    labels: BTreeMap<String, String>,
+) -> Option<Service> {
+    let ports: Vec<ServicePort> = spec
+        .containers
+        .iter()
+        .flat_map(|container| container.ports.iter().flatten())
+        .filter_map(|port| {
+            port.host_port.map(|host_port| ServicePort {
+                name: port.name.clone(),
+                port: host_port,
+                protocol: port.protocol.clone(),
+                target_port: Some(IntOrString::Int(port.container_port)),
+                ..ServicePort::default()
+            })
+        })
+        .collect();
+
+    (!ports.is_empty()).then(|| Service {
+        metadata: ObjectMeta {
+            name: Some(pod_name.to_owned()),
+            ..ObjectMeta::default()
+        },
+        spec: Some(ServiceSpec {
+            ports: Some(ports),
+            selector: Some(labels),
+            ..ServiceSpec::default()
+        }),
+        status: None,
+    })
+}
+
+/// Attempt to convert compose [`Configs`] into a [`ConfigMap`] with the given `name`.
+///
+/// Each config's identifier is used as its key in the [`ConfigMap`]'s data.
+/// Returns [`None`] if there are no `configs`.
+///
+/// # Errors
+///
+/// Returns an error if a config is external, has extensions, or its source has not been resolved
+/// to its content.
+fn configs_try_into_config_map(
+    configs: Configs,
+    name: String,
+) -> color_eyre::Result<Option<ConfigMap>> {
+    if configs.is_empty() {
+        return Ok(None);
+    }
+
+    let data = configs
+        .into_iter()
+        .map(|(id, config)| {
+            let Resource::Compose(Config {
+                source,
+                labels: _,
+                extensions,
+            }) = config
+            else {
+                bail!("external configs (`{id}`) are not supported");
+            };
+            ensure!(
+                extensions.is_empty(),
+                "compose extensions are not supported"
+            );
+            let config::Source::Content(content) = source else {
+                bail!("config `{id}` must be resolved to its content before converting");
+            };
+            Ok((id.into(), content))
+        })
+        .collect::<color_eyre::Result<_>>()?;
+
+    Ok(Some(ConfigMap {
+        metadata: ObjectMeta {
+            name: Some(name),
+            ..ObjectMeta::default()
+        },
+        data: Some(data),
+        ..ConfigMap::default()
+    }))
+}
+
+/// Attempt to convert compose [`Secrets`] into a [`Secret`] with the given `name`.
+///
+/// Each secret's identifier is used as its key in the [`Secret`]'s data. File secrets are read
+/// relative to the `project_directory` and environment secrets take their value from the
+/// `interpolator`'s variables. Returns [`None`] if there are no `secrets`.
+///
+/// # Errors
+///
+/// Returns an error if a secret is external or uses an unsupported option, a file could not be
+/// read, or an environment variable is not set.
+fn secrets_try_into_secret(
+    secrets: Secrets,
+    project_directory: &Path,
+    interpolator: &Interpolator,
+    name: String,
+) -> color_eyre::Result<Option<Secret>> {
+    if secrets.is_empty() {
+        return Ok(None);
+    }
+
+    let data = secrets
+        .into_iter()
+        .map(|(id, secret)| {
+            let Resource::Compose(compose_spec::Secret {
+                source,
+                labels: _,
+                driver,
+                driver_opts,
+                extensions,
+            }) = secret
+            else {
+                bail!("external secrets (`{id}`) are not supported");
+            };
+            ensure!(driver.is_none(), "`driver` is not supported, secret `{id}`");
+            ensure!(
+                driver_opts.is_empty(),
+                "`driver_opts` is not supported, secret `{id}`"
+            );
+            ensure!(
+                extensions.is_empty(),
+                "compose extensions are not supported"
+            );
+            let content = match source {
+                secret::Source::File(path) => {
+                    let path = project_directory.join(path);
+                    fs::read(&path).wrap_err_with(|| {
+                        format!("error reading file `{}` for secret `{id}`", path.display())
+                    })?
+                }
+                secret::Source::Environment(variable) => interpolator
+                    .variable(&variable)
+                    .ok_or_eyre(format!(
+                        "environment variable `{variable}` for secret `{id}` is not set"
+                    ))?
+                    .into_owned()
+                    .into_bytes(),
+            };
+            Ok((id.into(), ByteString(content)))
+        })
+        .collect::<color_eyre::Result<_>>()?;
+
+    Ok(Some(Secret {
+        metadata: ObjectMeta {
+            name: Some(name),
+            ..ObjectMeta::default()
+        },
+        data: Some(data),
+        ..Secret::default()
+    }))
 }
 
 impl Display for File {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         let Self {
             name: _,
-            pod,
+            pods,
+            services,
             persistent_volume_claims,
+            config_maps,
+            secret,
+            networks: _,
         } = self;
 
+        for config_map in config_maps {
+            f.write_str(&serde_yaml::to_string(config_map).map_err(|_| fmt::Error)?)?;
+            writeln!(f, "---")?;
+        }
+
+        if let Some(secret) = secret {
+            f.write_str(&serde_yaml::to_string(secret).map_err(|_| fmt::Error)?)?;
+            writeln!(f, "---")?;
+        }
+
         for volume in persistent_volume_claims {
             f.write_str(&serde_yaml::to_string(volume).map_err(|_| fmt::Error)?)?;
             writeln!(f, "---")?;
         }
 
-        f.write_str(&serde_yaml::to_string(pod).map_err(|_| fmt::Error)?)
+        for service in services {
+            f.write_str(&serde_yaml::to_string(service).map_err(|_| fmt::Error)?)?;
+            writeln!(f, "---")?;
+        }
+
+        for (index, pod) in pods.iter().enumerate() {
+            if index > 0 {
+                writeln!(f, "---")?;
+            }
+            f.write_str(&serde_yaml::to_string(pod).map_err(|_| fmt::Error)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_service_layout() {
+        let compose: Compose = serde_yaml::from_str(
+            "name: app\n\
+            services:\n  \
+                web:\n    \
+                    image: web\n    \
+                    ports: ['8080:80']\n  \
+                db:\n    \
+                    image: db\n",
+        )
+        .unwrap();
+        let options = Options {
+            layout: Layout::PerService,
+            ..Options::default()
+        };
+        let interpolator = Interpolator::new(Variables::default(), false);
+        let file =
+            File::try_from_compose(compose, Path::new(""), &interpolator, options, None).unwrap();
+
+        let pods: Vec<_> = file
+            .pods
+            .iter()
+            .map(|pod| pod.metadata.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(pods, ["app-web", "app-db"]);
+
+        let service = file.services.first().unwrap();
+        assert_eq!(file.services.len(), 1);
+        assert_eq!(service.metadata.name.as_deref(), Some("app-web"));
+        let spec = service.spec.as_ref().unwrap();
+        let port = spec.ports.as_ref().unwrap().first().unwrap();
+        assert_eq!(port.port, 8080);
+        assert_eq!(port.target_port, Some(IntOrString::Int(80)));
+        assert_eq!(spec.selector, file.pods.first().unwrap().metadata.labels);
+    }
+
+    #[test]
+    fn env_file_secret() {
+        let directory = std::env::temp_dir().join("podlet-kube-secret");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join(".env"), "PODLET_TEST_SECRET=abc\n").unwrap();
+        let interpolator =
+            Interpolator::new(Variables::from_env_files(&[], &directory).unwrap(), false);
+
+        let compose: Compose = serde_yaml::from_str(
+            "name: app\n\
+            services:\n  \
+                web:\n    \
+                    image: web\n    \
+                    secrets: [token]\n\
+            secrets:\n  \
+                token:\n    \
+                    environment: PODLET_TEST_SECRET\n",
+        )
+        .unwrap();
+        let file =
+            File::try_from_compose(compose, &directory, &interpolator, Options::default(), None)
+                .unwrap();
+
+        let data = file.secret.unwrap().data.unwrap();
+        assert_eq!(data.get("token"), Some(&ByteString(b"abc".to_vec())));
+    }
+
+    #[test]
+    fn networks() {
+        let compose: Compose = serde_yaml::from_str(
+            "name: app\n\
+            services:\n  \
+                web:\n    \
+                    image: web\n    \
+                    networks: [front, back]\n  \
+                db:\n    \
+                    image: db\n    \
+                    networks: [back]\n\
+            networks:\n  \
+                front:\n  \
+                back:\n    \
+                    external: true\n    \
+                    name: shared\n",
+        )
+        .unwrap();
+        let file = File::try_from(compose).unwrap();
+        assert_eq!(file.networks, ["front.network", "shared"]);
+
+        let compose: Compose = serde_yaml::from_str(
+            "name: app\n\
+            services:\n  \
+                web:\n    \
+                    image: web\n    \
+                    networks:\n      \
+                        front:\n        \
+                            aliases: [www]\n\
+            networks:\n  \
+                front:\n",
+        )
+        .unwrap();
+        assert!(File::try_from(compose).is_err());
     }
 }
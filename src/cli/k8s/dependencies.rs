@@ -0,0 +1,189 @@
+//! Ordering of the [`Service`]s in a Kubernetes pod according to their `depends_on` field.
+
+use std::mem;
+
+use color_eyre::eyre::{bail, ensure, eyre};
+use compose_spec::{
+    service::{Condition, Dependency},
+    Identifier, Service,
+};
+use indexmap::{IndexMap, IndexSet};
+
+/// Sort compose `services` so that each service comes after its dependencies, returning the
+/// services which are converted into init containers.
+///
+/// Services which another service depends on with the `service_completed_successfully` condition
+/// become init containers, which Kubernetes runs to completion, in order, before the pod's other
+/// containers are started. Other dependencies only set the order of the pod's containers.
+/// Dependencies which are not required and do not exist are ignored.
+///
+/// # Errors
+///
+/// Returns an error if a required dependency does not exist, an init container depends on a
+/// service which is not an init container, or the services' dependencies have a cycle.
+pub(super) fn sort(
+    services: &mut IndexMap<Identifier, Service>,
+) -> color_eyre::Result<IndexSet<Identifier>> {
+    let depends_on: IndexMap<Identifier, IndexMap<Identifier, Dependency>> = services
+        .iter_mut()
+        .map(|(name, service)| (name.clone(), mem::take(&mut service.depends_on).into_long()))
+        .collect();
+
+    let mut init = IndexSet::new();
+    let mut graph = IndexMap::with_capacity(depends_on.len());
+    for (name, dependencies) in &depends_on {
+        let mut service_dependencies = IndexSet::with_capacity(dependencies.len());
+        for (dependency, options) in dependencies {
+            if !depends_on.contains_key(dependency) {
+                ensure!(
+                    !options.required,
+                    "service `{name}` depends on service `{dependency}` which does not exist"
+                );
+                continue;
+            }
+            if options.condition == Condition::ServiceCompletedSuccessfully {
+                init.insert(dependency.clone());
+            }
+            service_dependencies.insert(dependency);
+        }
+        graph.insert(name, service_dependencies);
+    }
+
+    for name in &init {
+        if let Some(dependency) = graph
+            .get(name)
+            .into_iter()
+            .flatten()
+            .find(|dependency| !init.contains(**dependency))
+        {
+            bail!(
+                "service `{name}` is converted into an init container as another service depends \
+                    on it completing successfully, so it cannot depend on service `{dependency}` \
+                    which is not an init container"
+            );
+        }
+    }
+
+    let mut sorted = IndexSet::with_capacity(graph.len());
+    for name in graph.keys() {
+        visit(name, &graph, &mut IndexSet::new(), &mut sorted)?;
+    }
+
+    let mut unsorted = mem::take(services);
+    *services = sorted
+        .into_iter()
+        .filter_map(|name| unsorted.swap_remove_entry(name))
+        .collect();
+
+    Ok(init)
+}
+
+/// Visit the service `name` in a depth-first search of the dependency `graph`, adding it to
+/// `sorted` after all of its dependencies.
+///
+/// `path` is the chain of services which depend on `name`.
+///
+/// # Errors
+///
+/// Returns an error if `name` is already in the `path`, meaning there is a dependency cycle.
+fn visit<'a>(
+    name: &'a Identifier,
+    graph: &IndexMap<&'a Identifier, IndexSet<&'a Identifier>>,
+    path: &mut IndexSet<&'a Identifier>,
+    sorted: &mut IndexSet<&'a Identifier>,
+) -> color_eyre::Result<()> {
+    if sorted.contains(name) {
+        return Ok(());
+    }
+
+    if let Some(start) = path.get_index_of(name) {
+        let cycle: Vec<String> = path
+            .iter()
+            .skip(start)
+            .chain([&name])
+            .map(|name| format!("`{name}`"))
+            .collect();
+        return Err(eyre!(
+            "services have a dependency cycle: {}",
+            cycle.join(" -> ")
+        ));
+    }
+
+    path.insert(name);
+    for dependency in graph.get(name).into_iter().flatten() {
+        visit(dependency, graph, path, sorted)?;
+    }
+    path.pop();
+
+    sorted.insert(name);
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn services(yaml: &str) -> IndexMap<Identifier, Service> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn init_containers() {
+        let mut services = services(
+            "web:\n  \
+                image: web\n  \
+                depends_on:\n    \
+                    db:\n      \
+                        condition: service_started\n    \
+                    migrate:\n      \
+                        condition: service_completed_successfully\n\
+            db:\n  \
+                image: db\n\
+            migrate:\n  \
+                image: migrate\n  \
+                depends_on:\n    \
+                    setup:\n      \
+                        condition: service_completed_successfully\n    \
+                    missing:\n      \
+                        condition: service_started\n      \
+                        required: false\n\
+            setup:\n  \
+                image: setup\n",
+        );
+        let init = sort(&mut services).unwrap();
+
+        let order: Vec<_> = services.keys().map(Identifier::as_str).collect();
+        assert_eq!(order, ["db", "setup", "migrate", "web"]);
+        let init: Vec<_> = init.iter().map(Identifier::as_str).collect();
+        assert_eq!(init, ["migrate", "setup"]);
+    }
+
+    #[test]
+    fn errors() {
+        let cycle = "one:\n  \
+                image: one\n  \
+                depends_on: [two]\n\
+            two:\n  \
+                image: two\n  \
+                depends_on: [one]\n";
+        assert!(sort(&mut services(cycle)).is_err());
+
+        let missing = "one:\n  \
+                image: one\n  \
+                depends_on: [two]\n";
+        assert!(sort(&mut services(missing)).is_err());
+
+        let init_depends_on_container = "one:\n  \
+                image: one\n  \
+                depends_on:\n    \
+                    two:\n      \
+                        condition: service_completed_successfully\n\
+            two:\n  \
+                image: two\n  \
+                depends_on: [three]\n\
+            three:\n  \
+                image: three\n";
+        assert!(sort(&mut services(init_depends_on_container)).is_err());
+    }
+}
@@ -0,0 +1,1097 @@
+//! Provides the `podlet kube import` subcommand, see [`Import`].
+//!
+//! Kubernetes YAML documents are converted into native Quadlet files instead of being wrapped by a
+//! `.kube` file: a `.pod` file for each pod, a `.container` file for each of the pod's containers,
+//! and a `.volume` file for each [`PersistentVolumeClaim`].
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    fs, mem,
+    path::PathBuf,
+};
+
+use clap::Args;
+use color_eyre::eyre::{bail, eyre, OptionExt, WrapErr};
+use compose_spec::service::Restart;
+use k8s_openapi::{
+    api::{
+        apps::v1::Deployment,
+        core::v1::{
+            ConfigMap, Container, ContainerPort, EnvFromSource, EnvVar, EnvVarSource,
+            PersistentVolumeClaim, Pod, PodSpec, PodTemplateSpec, Probe, SecurityContext, Volume,
+            VolumeMount,
+        },
+    },
+    apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::{compose::warn, unit::Unit},
+    escape::command_join,
+    quadlet::{self, container::PullPolicy, Globals, Install},
+};
+
+/// [`Args`] for `podlet kube import`.
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct Import {
+    /// The Kubernetes YAML file to convert
+    ///
+    /// The file may contain multiple YAML documents. `Pod`, `Deployment`, `PersistentVolumeClaim`,
+    /// and `ConfigMap` documents are converted, other documents are skipped.
+    ///
+    /// A `.pod` file is created for each pod or deployment, and a `.container` file for each of its
+    /// containers. Persistent volume claims become `.volume` files. Environment variables from
+    /// config maps are added with `Environment=`. Environment variables from a secret's key are
+    /// added with `Secret=`, the Podman secret must be named "{secret}-{key}".
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+}
+
+impl Import {
+    /// Read the Kubernetes YAML file and convert its documents into [`quadlet::File`]s.
+    ///
+    /// A report of the fields which could not be converted is printed to stderr.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be read or parsed, or a document could not be
+    /// converted.
+    pub fn try_into_quadlet_files(
+        self,
+        unit: Option<&Unit>,
+        install: Option<&Install>,
+    ) -> color_eyre::Result<Vec<quadlet::File>> {
+        let contents = fs::read_to_string(&self.file)
+            .wrap_err_with(|| format!("error reading `{}`", self.file.display()))?;
+
+        let mut report = Report::default();
+        let files = Documents::parse(&contents, &mut report)?.try_into_quadlet_files(
+            unit,
+            install,
+            &mut report,
+        )?;
+
+        if !report.is_empty() {
+            warn(report);
+        }
+
+        Ok(files)
+    }
+}
+
+/// Supported Kubernetes documents read from a YAML file.
+#[derive(Debug, Default)]
+struct Documents {
+    /// Pod names paired with their spec, from `Pod` and `Deployment` documents.
+    pods: Vec<(String, PodSpec)>,
+
+    /// `PersistentVolumeClaim` documents.
+    persistent_volume_claims: Vec<PersistentVolumeClaim>,
+
+    /// Data of `ConfigMap` documents, by name.
+    config_maps: HashMap<String, BTreeMap<String, String>>,
+}
+
+impl Documents {
+    /// Parse the documents of a Kubernetes YAML file's `contents`.
+    ///
+    /// Unsupported documents, and document fields which are not converted, are added to the
+    /// `report`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a document could not be parsed or is missing a required field.
+    fn parse(contents: &str, report: &mut Report) -> color_eyre::Result<Self> {
+        let mut documents = Self::default();
+
+        for document in serde_yaml::Deserializer::from_str(contents) {
+            let value =
+                serde_yaml::Value::deserialize(document).wrap_err("error parsing YAML document")?;
+            if value.is_null() {
+                continue;
+            }
+            let kind = value
+                .get("kind")
+                .and_then(serde_yaml::Value::as_str)
+                .ok_or_eyre("YAML document does not have a `kind`")?
+                .to_owned();
+
+            match kind.as_str() {
+                "Pod" => {
+                    let pod = serde_yaml::from_value(value).wrap_err("error parsing pod")?;
+                    documents.add_pod(pod, report)?;
+                }
+                "Deployment" => {
+                    let deployment =
+                        serde_yaml::from_value(value).wrap_err("error parsing deployment")?;
+                    documents.add_deployment(deployment, report)?;
+                }
+                "PersistentVolumeClaim" => documents.persistent_volume_claims.push(
+                    serde_yaml::from_value(value)
+                        .wrap_err("error parsing persistent volume claim")?,
+                ),
+                "ConfigMap" => {
+                    let config_map =
+                        serde_yaml::from_value(value).wrap_err("error parsing config map")?;
+                    documents.add_config_map(config_map, report)?;
+                }
+                _ => {
+                    let name = value
+                        .get("metadata")
+                        .and_then(|metadata| metadata.get("name"))
+                        .and_then(serde_yaml::Value::as_str)
+                        .unwrap_or_default();
+                    report.add(format!("{kind} `{name}`"), ["document".to_owned()]);
+                }
+            }
+        }
+
+        Ok(documents)
+    }
+
+    /// Add a [`Pod`] document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod does not have a name or spec.
+    fn add_pod(
+        &mut self,
+        Pod {
+            metadata,
+            spec,
+            status: _,
+        }: Pod,
+        report: &mut Report,
+    ) -> color_eyre::Result<()> {
+        let name = take_name(metadata, "pod", report)?;
+        let spec = spec.ok_or_else(|| eyre!("pod `{name}` must have a `spec`"))?;
+        self.pods.push((name, spec));
+        Ok(())
+    }
+
+    /// Add the pod template of a [`Deployment`] document, the pod is named after the deployment.
+    ///
+    /// The pod template's labels are used by the deployment's `selector`, which is not needed for
+    /// Quadlet files. Other deployment fields, including `replicas` greater than one, are added to
+    /// the `report`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the deployment does not have a name or pod template spec.
+    fn add_deployment(
+        &mut self,
+        Deployment {
+            metadata,
+            spec,
+            status: _,
+        }: Deployment,
+        report: &mut Report,
+    ) -> color_eyre::Result<()> {
+        let name = take_name(metadata, "deployment", report)?;
+        let object = format!("deployment `{name}`");
+        let mut spec = spec.ok_or_else(|| eyre!("{object} must have a `spec`"))?;
+
+        if spec.replicas.take().is_some_and(|replicas| replicas > 1) {
+            report.add(&object, ["spec.replicas".to_owned()]);
+        }
+        spec.selector = LabelSelector::default();
+
+        let PodTemplateSpec {
+            metadata,
+            spec: pod,
+        } = mem::take(&mut spec.template);
+        if let Some(mut metadata) = metadata {
+            metadata.labels = None;
+            report.add(
+                &object,
+                set_fields(&metadata)
+                    .into_iter()
+                    .map(|field| format!("spec.template.metadata.{field}")),
+            );
+        }
+        report.add(
+            &object,
+            set_fields(&spec)
+                .into_iter()
+                .map(|field| format!("spec.{field}")),
+        );
+
+        let pod = pod.ok_or_else(|| eyre!("{object} must have a `spec.template.spec`"))?;
+        self.pods.push((name, pod));
+        Ok(())
+    }
+
+    /// Add the `data` of a [`ConfigMap`] document.
+    ///
+    /// `binaryData` and other fields are added to the `report`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config map does not have a name.
+    fn add_config_map(
+        &mut self,
+        mut config_map: ConfigMap,
+        report: &mut Report,
+    ) -> color_eyre::Result<()> {
+        let name = take_name(mem::take(&mut config_map.metadata), "config map", report)?;
+        let data = config_map.data.take().unwrap_or_default();
+        report.add(format!("config map `{name}`"), set_fields(&config_map));
+        self.config_maps.insert(name, data);
+        Ok(())
+    }
+
+    /// Convert the documents into [`quadlet::File`]s.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a pod could not be converted.
+    fn try_into_quadlet_files(
+        mut self,
+        unit: Option<&Unit>,
+        install: Option<&Install>,
+        report: &mut Report,
+    ) -> color_eyre::Result<Vec<quadlet::File>> {
+        let claims: HashSet<String> = self
+            .persistent_volume_claims
+            .iter()
+            .filter_map(|claim| claim.metadata.name.clone())
+            .collect();
+
+        let mut files = Vec::new();
+        for (name, spec) in mem::take(&mut self.pods) {
+            files.extend(
+                self.pod_try_into_quadlet_files(&name, spec, &claims, unit, install, report)
+                    .wrap_err_with(|| format!("error converting pod `{name}`"))?,
+            );
+        }
+
+        for claim in self.persistent_volume_claims {
+            let (name, volume) = persistent_volume_claim_into_volume(claim, report);
+            files.push(quadlet::File {
+                name,
+                unit: unit.cloned(),
+                resource: volume.into(),
+                globals: Globals::default(),
+                service: None,
+                install: install.cloned(),
+            });
+        }
+
+        Ok(files)
+    }
+
+    /// Convert a pod's `spec` into a `.pod` [`quadlet::File`] and a `.container`
+    /// [`quadlet::File`] for each of its containers.
+    ///
+    /// `claims` are the names of the persistent volume claims which have a `.volume` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod's `restartPolicy` is unknown or a container could not be
+    /// converted.
+    fn pod_try_into_quadlet_files(
+        &self,
+        name: &str,
+        mut spec: PodSpec,
+        claims: &HashSet<String>,
+        unit: Option<&Unit>,
+        install: Option<&Install>,
+        report: &mut Report,
+    ) -> color_eyre::Result<Vec<quadlet::File>> {
+        let containers = mem::take(&mut spec.containers);
+        let volumes: HashMap<String, Volume> = spec
+            .volumes
+            .take()
+            .into_iter()
+            .flatten()
+            .map(|volume| (volume.name.clone(), volume))
+            .collect();
+
+        let restart = match spec.restart_policy.take().as_deref() {
+            None | Some("Always") => Restart::Always,
+            Some("OnFailure") => Restart::OnFailure,
+            Some("Never") => Restart::No,
+            Some(restart_policy) => bail!("unknown `restartPolicy` `{restart_policy}`"),
+        };
+
+        let mut pod = quadlet::Pod {
+            pod_name: Some(name.to_owned()),
+            ..quadlet::Pod::default()
+        };
+        if spec.host_network.take().unwrap_or_default() {
+            pod.network.push("host".to_owned());
+        }
+
+        report.add(
+            format!("pod `{name}`"),
+            set_fields(&spec)
+                .into_iter()
+                .map(|field| format!("spec.{field}")),
+        );
+
+        let mut files = Vec::with_capacity(containers.len() + 1);
+        for container in containers {
+            let container_name = container.name.clone();
+            let mut container = self
+                .container_try_into_quadlet(container, name, &volumes, claims, report)
+                .wrap_err_with(|| format!("error converting container `{container_name}`"))?;
+            pod.publish_port
+                .extend(mem::take(&mut container.publish_port));
+            container.pod = Some(format!("{name}.pod"));
+            container.container_name = Some(format!("{name}-{container_name}"));
+
+            files.push(quadlet::File {
+                name: format!("{name}-{container_name}"),
+                unit: unit.cloned(),
+                resource: container.into(),
+                globals: Globals::default(),
+                service: Some(restart.into()),
+                install: install.cloned(),
+            });
+        }
+
+        files.push(quadlet::File {
+            name: name.to_owned(),
+            unit: unit.cloned(),
+            resource: pod.into(),
+            globals: Globals::default(),
+            service: None,
+            install: install.cloned(),
+        });
+
+        Ok(files)
+    }
+
+    /// Attempt to convert a Kubernetes [`Container`] in the pod `pod_name` into a
+    /// [`quadlet::Container`].
+    ///
+    /// Published ports are added to the container's `publish_port`, they must be moved to the pod.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container does not have an image, or a field could not be
+    /// converted.
+    fn container_try_into_quadlet(
+        &self,
+        mut container: Container,
+        pod_name: &str,
+        volumes: &HashMap<String, Volume>,
+        claims: &HashSet<String>,
+        report: &mut Report,
+    ) -> color_eyre::Result<quadlet::Container> {
+        let object = format!("pod `{pod_name}`, container `{}`", container.name);
+        container.name.clear();
+
+        let mut quadlet = quadlet::Container {
+            image: container.image.take().ok_or_eyre("`image` is required")?,
+            entrypoint: container
+                .command
+                .take()
+                .map(|command| serde_json::to_string(&command))
+                .transpose()
+                .wrap_err("error serializing `command` as JSON")?,
+            exec: container.args.take().map(command_join),
+            publish_port: container
+                .ports
+                .take()
+                .into_iter()
+                .flatten()
+                .filter_map(port_into_publish_port)
+                .collect(),
+            pull: container
+                .image_pull_policy
+                .take()
+                .map(|policy| match policy.as_str() {
+                    "Always" => Ok(PullPolicy::Always),
+                    "IfNotPresent" => Ok(PullPolicy::Missing),
+                    "Never" => Ok(PullPolicy::Never),
+                    _ => Err(eyre!("unknown `imagePullPolicy` `{policy}`")),
+                })
+                .transpose()?,
+            working_dir: container.working_dir.take().map(Into::into),
+            ..quadlet::Container::default()
+        };
+
+        for env in container.env.take().into_iter().flatten() {
+            self.add_env(&mut quadlet, env, &object, report)?;
+        }
+        for env_from in container.env_from.take().into_iter().flatten() {
+            self.add_env_from(&mut quadlet, env_from, &object, report)?;
+        }
+
+        for volume_mount in container.volume_mounts.take().into_iter().flatten() {
+            add_volume_mount(&mut quadlet, volume_mount, volumes, claims, &object, report)
+                .wrap_err("error converting `volumeMounts`")?;
+        }
+
+        if let Some(security_context) = container.security_context.take() {
+            add_security_context(&mut quadlet, security_context, &object, report);
+        }
+
+        if let Some(probe) = container.liveness_probe.take() {
+            add_liveness_probe(&mut quadlet, probe, &object, report)
+                .wrap_err("error converting `livenessProbe`")?;
+        }
+
+        report.add(object, set_fields(&container));
+
+        Ok(quadlet)
+    }
+
+    /// Add an [`EnvVar`] to a [`quadlet::Container`] with `Environment=` or `Secret=`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a referenced config map or key does not exist and is not optional.
+    fn add_env(
+        &self,
+        quadlet: &mut quadlet::Container,
+        EnvVar {
+            name,
+            value,
+            value_from,
+        }: EnvVar,
+        object: &str,
+        report: &mut Report,
+    ) -> color_eyre::Result<()> {
+        let Some(EnvVarSource {
+            config_map_key_ref,
+            field_ref,
+            resource_field_ref,
+            secret_key_ref,
+        }) = value_from
+        else {
+            quadlet
+                .environment
+                .push(format!("{name}={}", value.unwrap_or_default()));
+            return Ok(());
+        };
+
+        if let Some(selector) = config_map_key_ref {
+            let config_map = selector.name.unwrap_or_default();
+            let value = self
+                .config_maps
+                .get(&config_map)
+                .and_then(|data| data.get(&selector.key));
+            match value {
+                Some(value) => quadlet.environment.push(format!("{name}={value}")),
+                None if selector.optional.unwrap_or_default() => {}
+                None => bail!(
+                    "key `{}` of config map `{config_map}`, for environment variable `{name}`, \
+                        does not exist",
+                    selector.key,
+                ),
+            }
+        } else if let Some(selector) = secret_key_ref {
+            quadlet.secret.push(format!(
+                "{}-{},type=env,target={name}",
+                selector.name.unwrap_or_default(),
+                selector.key
+            ));
+        } else if field_ref.is_some() || resource_field_ref.is_some() {
+            report.add(object, [format!("env.{name}.valueFrom")]);
+        }
+
+        Ok(())
+    }
+
+    /// Add the environment variables of an [`EnvFromSource`] config map to a
+    /// [`quadlet::Container`] with `Environment=`.
+    ///
+    /// Environment variables from a secret are added to the `report`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config map does not exist and is not optional.
+    fn add_env_from(
+        &self,
+        quadlet: &mut quadlet::Container,
+        EnvFromSource {
+            config_map_ref,
+            prefix,
+            secret_ref,
+        }: EnvFromSource,
+        object: &str,
+        report: &mut Report,
+    ) -> color_eyre::Result<()> {
+        if let Some(config_map_ref) = config_map_ref {
+            let config_map = config_map_ref.name.unwrap_or_default();
+            match self.config_maps.get(&config_map) {
+                Some(data) => quadlet.environment.extend(data.iter().map(|(key, value)| {
+                    format!("{}{key}={value}", prefix.as_deref().unwrap_or_default())
+                })),
+                None if config_map_ref.optional.unwrap_or_default() => {}
+                None => bail!("config map `{config_map}`, for `envFrom`, does not exist"),
+            }
+        }
+
+        if let Some(secret_ref) = secret_ref {
+            report.add(
+                object,
+                [format!(
+                    "envFrom.secretRef.{}",
+                    secret_ref.name.unwrap_or_default()
+                )],
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Take the name from an object's `metadata`, adding other set metadata fields to the `report`.
+///
+/// # Errors
+///
+/// Returns an error if the object does not have a name.
+fn take_name(
+    mut metadata: ObjectMeta,
+    kind: &str,
+    report: &mut Report,
+) -> color_eyre::Result<String> {
+    let name = metadata
+        .name
+        .take()
+        .ok_or_else(|| eyre!("{kind} must have a `name`"))?;
+    report.add(
+        format!("{kind} `{name}`"),
+        set_fields(&metadata)
+            .into_iter()
+            .map(|field| format!("metadata.{field}")),
+    );
+    Ok(name)
+}
+
+/// Convert a [`ContainerPort`] into a `PublishPort=` value.
+///
+/// Returns [`None`] if the port is not published with a `hostPort`.
+fn port_into_publish_port(
+    ContainerPort {
+        container_port,
+        host_ip,
+        host_port,
+        name: _,
+        protocol,
+    }: ContainerPort,
+) -> Option<String> {
+    let host_port = host_port?;
+    let host_ip = host_ip
+        .map(|host_ip| format!("{host_ip}:"))
+        .unwrap_or_default();
+    let protocol = protocol
+        .filter(|protocol| protocol != "TCP")
+        .map(|protocol| format!("/{}", protocol.to_lowercase()))
+        .unwrap_or_default();
+    Some(format!("{host_ip}{host_port}:{container_port}{protocol}"))
+}
+
+/// Add a [`VolumeMount`] to a [`quadlet::Container`] as a `Volume=` or `Tmpfs=`.
+///
+/// `persistentVolumeClaim` volumes reference the claim's `.volume` file if its name is in `claims`,
+/// or a named volume otherwise. Mounts of other unsupported volume types are added to the `report`.
+///
+/// # Errors
+///
+/// Returns an error if the mounted volume is not in the pod's `volumes`.
+fn add_volume_mount(
+    quadlet: &mut quadlet::Container,
+    VolumeMount {
+        mount_path,
+        mount_propagation,
+        name,
+        read_only,
+        recursive_read_only,
+        sub_path,
+        sub_path_expr,
+    }: VolumeMount,
+    volumes: &HashMap<String, Volume>,
+    claims: &HashSet<String>,
+    object: &str,
+    report: &mut Report,
+) -> color_eyre::Result<()> {
+    let mut volume = volumes
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| eyre!("volume `{name}` is not in the pod's `volumes`"))?;
+    volume.name.clear();
+
+    if recursive_read_only.is_some() || sub_path.is_some() || sub_path_expr.is_some() {
+        report.add(object, [format!("volumeMounts.{name}")]);
+        return Ok(());
+    }
+
+    let mut options = Vec::new();
+    if read_only.unwrap_or_default() {
+        options.push("ro");
+    }
+    match mount_propagation.as_deref() {
+        None | Some("None") => {}
+        Some("HostToContainer") => options.push("rslave"),
+        Some("Bidirectional") => options.push("rshared"),
+        Some(propagation) => bail!("unknown `mountPropagation` `{propagation}`"),
+    }
+
+    let source = if let Some(claim) = volume.persistent_volume_claim.take() {
+        if claim.read_only.unwrap_or_default() && !options.contains(&"ro") {
+            options.push("ro");
+        }
+        if claims.contains(&claim.claim_name) {
+            Some(format!("{}.volume", claim.claim_name))
+        } else {
+            Some(claim.claim_name)
+        }
+    } else if let Some(host_path) = volume.host_path.take() {
+        if let Some(type_) = host_path.type_.filter(|type_| type_.ends_with("OrCreate")) {
+            report.add(object, [format!("volumes.{name}.hostPath.type: {type_}")]);
+        }
+        Some(host_path.path)
+    } else if let Some(mut empty_dir) = volume.empty_dir.take() {
+        if empty_dir.medium.take().as_deref() == Some("Memory") {
+            quadlet.tmpfs.push(mount_path);
+            report.add(
+                object,
+                set_fields(&empty_dir)
+                    .into_iter()
+                    .map(|field| format!("volumes.{name}.emptyDir.{field}")),
+            );
+            return Ok(());
+        }
+        None
+    } else {
+        report.add(
+            object,
+            set_fields(&volume)
+                .into_iter()
+                .map(|field| format!("volumes.{name}.{field}")),
+        );
+        return Ok(());
+    };
+
+    let mut volume = source
+        .map(|source| format!("{source}:"))
+        .unwrap_or_default();
+    volume.push_str(&mount_path);
+    if !options.is_empty() {
+        volume.push(':');
+        volume.push_str(&options.join(","));
+    }
+    quadlet.volume.push(
+        volume
+            .parse()
+            .wrap_err_with(|| format!("error parsing volume `{volume}`"))?,
+    );
+
+    Ok(())
+}
+
+/// Add a container's [`SecurityContext`] to a [`quadlet::Container`].
+///
+/// Unsupported fields are added to the `report`.
+fn add_security_context(
+    quadlet: &mut quadlet::Container,
+    mut security_context: SecurityContext,
+    object: &str,
+    report: &mut Report,
+) {
+    if let Some(capabilities) = security_context.capabilities.take() {
+        quadlet.add_capability = capabilities.add.unwrap_or_default();
+        quadlet.drop_capability = capabilities.drop.unwrap_or_default();
+    }
+
+    if security_context.allow_privilege_escalation.take() == Some(false) {
+        quadlet.no_new_privileges = true;
+    }
+
+    if security_context.privileged.take().unwrap_or_default() {
+        quadlet.podman_args = Some("--privileged".to_owned());
+    }
+
+    quadlet.read_only = security_context
+        .read_only_root_filesystem
+        .take()
+        .unwrap_or_default();
+    quadlet.user = security_context
+        .run_as_user
+        .take()
+        .map(|user| user.to_string());
+    quadlet.group = security_context
+        .run_as_group
+        .take()
+        .map(|group| group.to_string());
+
+    if let Some(se_linux_options) = &mut security_context.se_linux_options {
+        quadlet.security_label_level = se_linux_options.level.take();
+        quadlet.security_label_type = se_linux_options.type_.take();
+    }
+
+    report.add(
+        object,
+        set_fields(&security_context)
+            .into_iter()
+            .map(|field| format!("securityContext.{field}")),
+    );
+}
+
+/// Add a container's liveness [`Probe`] to a [`quadlet::Container`] as its health check.
+///
+/// Only `exec` probes are supported, other fields are added to the `report`.
+///
+/// # Errors
+///
+/// Returns an error if the probe's command could not be serialized.
+fn add_liveness_probe(
+    quadlet: &mut quadlet::Container,
+    mut probe: Probe,
+    object: &str,
+    report: &mut Report,
+) -> color_eyre::Result<()> {
+    let seconds = |seconds: Option<i32>| seconds.map(|seconds| format!("{seconds}s"));
+
+    if let Some(command) = probe.exec.take().and_then(|exec| exec.command) {
+        quadlet.health_cmd =
+            Some(serde_json::to_string(&command).wrap_err("error serializing `command` as JSON")?);
+        quadlet.health_interval = seconds(probe.period_seconds.take());
+        quadlet.health_timeout = seconds(probe.timeout_seconds.take());
+        quadlet.health_start_period = seconds(probe.initial_delay_seconds.take());
+        quadlet.health_retries = probe
+            .failure_threshold
+            .take()
+            .and_then(|retries| retries.try_into().ok());
+    }
+
+    report.add(
+        object,
+        set_fields(&probe)
+            .into_iter()
+            .map(|field| format!("livenessProbe.{field}")),
+    );
+
+    Ok(())
+}
+
+/// Convert a [`PersistentVolumeClaim`] into a [`quadlet::Volume`] and its file name.
+///
+/// The volume keeps the claim's name. Podman's `volume.podman.io/*` annotations are converted into
+/// the corresponding Quadlet options, other annotations and the claim's `spec`, such as its storage
+/// request, are added to the `report`.
+fn persistent_volume_claim_into_volume(
+    PersistentVolumeClaim {
+        mut metadata,
+        spec,
+        status: _,
+    }: PersistentVolumeClaim,
+    report: &mut Report,
+) -> (String, quadlet::Volume) {
+    let name = metadata.name.take().unwrap_or_default();
+    let mut volume = quadlet::Volume {
+        volume_name: Some(name.clone()),
+        label: metadata
+            .labels
+            .take()
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect(),
+        ..quadlet::Volume::default()
+    };
+
+    for (key, value) in metadata.annotations.take().into_iter().flatten() {
+        match key.as_str() {
+            "volume.podman.io/driver" => volume.driver = Some(value),
+            "volume.podman.io/device" => volume.device = Some(value.into()),
+            "volume.podman.io/type" => volume.fs_type = Some(value),
+            "volume.podman.io/uid" => volume.user = Some(value),
+            "volume.podman.io/gid" => volume.group = Some(value),
+            "volume.podman.io/mount-options" => volume.options = Some(value),
+            "volume.podman.io/image" => volume.image = Some(value),
+            _ => report.add(
+                format!("persistent volume claim `{name}`"),
+                [format!("metadata.annotations.{key}")],
+            ),
+        }
+    }
+
+    report.add(
+        format!("persistent volume claim `{name}`"),
+        set_fields(&metadata)
+            .into_iter()
+            .map(|field| format!("metadata.{field}"))
+            .chain(
+                spec.iter()
+                    .flat_map(set_fields)
+                    .map(|field| format!("spec.{field}")),
+            ),
+    );
+
+    (name, volume)
+}
+
+/// Names of the fields of a Kubernetes object which are set.
+///
+/// Used to report the fields which were not converted, so converted fields must be taken from the
+/// object first. Fields which are null, or an empty string, list, or map are not set. The
+/// `apiVersion` and `kind` of top-level objects are skipped.
+fn set_fields(object: &impl Serialize) -> Vec<String> {
+    let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(object) else {
+        return Vec::new();
+    };
+    fields
+        .into_iter()
+        .filter(|(field, _)| field != "apiVersion" && field != "kind")
+        .filter(|(_, value)| match value {
+            serde_json::Value::Null => false,
+            serde_json::Value::String(value) => !value.is_empty(),
+            serde_json::Value::Array(value) => !value.is_empty(),
+            serde_json::Value::Object(value) => !value.is_empty(),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => true,
+        })
+        .map(|(field, _)| field)
+        .collect()
+}
+
+/// Fields of Kubernetes objects which could not be converted into Quadlet files.
+#[derive(Debug, Default)]
+struct Report {
+    /// Dropped fields paired with a description of their object.
+    dropped: Vec<(String, String)>,
+}
+
+impl Report {
+    /// Add the `fields` dropped from `object` to the report.
+    fn add(&mut self, object: impl Display, fields: impl IntoIterator<Item = String>) {
+        let object = object.to_string();
+        self.dropped
+            .extend(fields.into_iter().map(|field| (object.clone(), field)));
+    }
+
+    /// Returns `true` if no fields were dropped.
+    fn is_empty(&self) -> bool {
+        self.dropped.is_empty()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "dropped {} field(s) which could not be converted into Quadlet files:",
+            self.dropped.len()
+        )?;
+        for (object, field) in &self.dropped {
+            write!(f, "\n  - {object}, field `{field}`")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = "\
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: settings
+data:
+  MODE: prod
+---
+apiVersion: v1
+kind: PersistentVolumeClaim
+metadata:
+  name: data
+  annotations:
+    volume.podman.io/driver: local
+---
+apiVersion: v1
+kind: Pod
+metadata:
+  name: web
+spec:
+  restartPolicy: OnFailure
+  volumes:
+    - name: data
+      persistentVolumeClaim:
+        claimName: data
+    - name: cache
+      emptyDir:
+        medium: Memory
+  containers:
+    - name: app
+      image: app
+      args: [serve, --port, '80']
+      ports:
+        - containerPort: 80
+          hostPort: 8080
+          protocol: UDP
+      env:
+        - name: MODE
+          valueFrom:
+            configMapKeyRef:
+              name: settings
+              key: MODE
+        - name: PASSWORD
+          valueFrom:
+            secretKeyRef:
+              name: db
+              key: password
+      volumeMounts:
+        - name: data
+          mountPath: /data
+          readOnly: true
+        - name: cache
+          mountPath: /cache
+      resources:
+        limits:
+          memory: 1Gi
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: web
+";
+
+    fn import(yaml: &str) -> color_eyre::Result<(Vec<String>, Report)> {
+        let mut report = Report::default();
+        let files = Documents::parse(yaml, &mut report)?
+            .try_into_quadlet_files(None, None, &mut report)?
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        Ok((files, report))
+    }
+
+    #[test]
+    fn pod() {
+        let (files, report) = import(YAML).unwrap();
+        assert_eq!(
+            files,
+            [
+                "[Container]\n\
+                ContainerName=web-app\n\
+                Environment=MODE=prod\n\
+                Exec=serve --port 80\n\
+                Image=app\n\
+                Pod=web.pod\n\
+                Secret=db-password,type=env,target=PASSWORD\n\
+                Tmpfs=/cache\n\
+                Volume=data.volume:/data:ro\n\
+                \n\
+                [Service]\n\
+                Restart=on-failure\n",
+                "[Pod]\n\
+                PodName=web\n\
+                PublishPort=8080:80/udp\n",
+                "[Volume]\n\
+                Driver=local\n\
+                VolumeName=data\n",
+            ]
+        );
+
+        let dropped: Vec<_> = report
+            .dropped
+            .iter()
+            .map(|(object, field)| format!("{object}, {field}"))
+            .collect();
+        assert_eq!(
+            dropped,
+            [
+                "Service `web`, document",
+                "pod `web`, container `app`, resources",
+            ]
+        );
+    }
+
+    #[test]
+    fn report() {
+        let yaml = "\
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: settings
+immutable: true
+binaryData:
+  key: aGVsbG8=
+---
+apiVersion: v1
+kind: PersistentVolumeClaim
+metadata:
+  name: data
+spec:
+  accessModes: [ReadWriteOnce]
+  storageClassName: fast
+  resources:
+    requests:
+      storage: 1Gi
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 1
+  strategy:
+    type: Recreate
+  selector:
+    matchLabels:
+      app: web
+  template:
+    metadata:
+      labels:
+        app: web
+      annotations:
+        io.podman.annotations.init/app: 'TRUE'
+    spec:
+      containers:
+        - name: app
+          image: app
+";
+        let (_, report) = import(yaml).unwrap();
+
+        let dropped: Vec<_> = report
+            .dropped
+            .iter()
+            .map(|(object, field)| format!("{object}, {field}"))
+            .collect();
+        assert_eq!(
+            dropped,
+            [
+                "config map `settings`, binaryData",
+                "config map `settings`, immutable",
+                "deployment `web`, spec.template.metadata.annotations",
+                "deployment `web`, spec.strategy",
+                "persistent volume claim `data`, spec.accessModes",
+                "persistent volume claim `data`, spec.resources",
+                "persistent volume claim `data`, spec.storageClassName",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_config_map() {
+        let yaml = "\
+apiVersion: v1
+kind: Pod
+metadata:
+  name: web
+spec:
+  containers:
+    - name: app
+      image: app
+      env:
+        - name: MODE
+          valueFrom:
+            configMapKeyRef:
+              name: settings
+              key: MODE
+";
+        assert!(import(yaml).is_err());
+
+        let optional = format!("{yaml}              optional: true\n");
+        let (files, _) = import(&optional).unwrap();
+        assert!(!files.first().unwrap().contains("Environment="));
+    }
+}
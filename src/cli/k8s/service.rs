@@ -1,58 +1,77 @@
 //! [`Service`] is created from a [`compose_spec::Service`] and then added to a [`PodSpec`].
 
+mod annotations;
 mod mount;
+mod pod;
 
-use std::{collections::BTreeMap, net::IpAddr, time::Duration};
-
-use color_eyre::{
-    eyre::{bail, ensure, eyre, OptionExt, WrapErr},
-    Section,
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    time::Duration,
 };
+
+use color_eyre::eyre::{bail, ensure, eyre, OptionExt, WrapErr};
 use compose_spec::{
     service::{
         build::Context,
         device::CgroupRule,
+        env_file,
         healthcheck::{self, Test},
         ports::{self, Port, Protocol},
         AbsolutePath, BlkioConfig, Build, ByteValue, Cgroup, Command, ConfigOrSecret, CpuSet, Cpus,
-        CredentialSpec, DependsOn, Deploy, Develop, Device, EnvFile, Expose, Extends, Healthcheck,
-        Hostname, Image, Ipc, Limit, Link, Logging, MacAddress, NetworkConfig, OomScoreAdj,
-        Percent, Platform, Ports, PullPolicy, Restart, Ulimits, UserOrGroup, Uts, Volumes,
-        VolumesFrom,
+        CredentialSpec, Deploy, Develop, Device, EnvFile, Expose, Extends, Healthcheck, Hostname,
+        Image, Limit, Link, Logging, MacAddress, NetworkConfig, OomScoreAdj, Percent, Platform,
+        Ports, PullPolicy, UserOrGroup, Uts, Volumes, VolumesFrom,
     },
-    Extensions, Identifier, ItemOrList, ListOrMap, Map, ShortOrLong,
+    Extensions, Identifier, InvalidMapKeyError, ItemOrList, ListOrMap, Map, ShortOrLong,
 };
-use indexmap::{IndexMap, IndexSet};
+use indexmap::IndexSet;
 use k8s_openapi::{
     api::core::v1::{
-        Capabilities, Container, ContainerPort, EnvVar, ExecAction, PodSpec, Probe,
-        ResourceRequirements, SELinuxOptions, SecurityContext,
+        Capabilities, ConfigMap, ConfigMapEnvSource, Container, ContainerPort, EnvFromSource,
+        EnvVar, ExecAction, Pod, PodSpec, Probe, ResourceRequirements, SELinuxOptions,
+        SecurityContext,
     },
-    apimachinery::pkg::api::resource::Quantity,
+    apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::ObjectMeta},
 };
 
 use crate::cli::{
-    compose::command_try_into_vec,
+    compose::{command_try_into_vec, Variables},
     container::security_opt::{LabelOpt, SecurityOpt},
+    lenient::{ensure_empty_or_drop, UnsupportedField},
 };
 
-use self::mount::tmpfs_and_volumes_try_into_volume_mounts;
+use super::BindMode;
 
-/// Fields from a [`compose_spec::Service`] which will be [added](Service::add_to_pod_spec()) to a
-/// [`PodSpec`]'s [`Container`]s and [`Volume`](k8s_openapi::api::core::v1::Volume)s.
+use self::{
+    annotations::PodmanAnnotations,
+    mount::{
+        configs_try_into_volume_mounts, secrets_try_into_volume_mounts,
+        tmpfs_and_volumes_try_into_volume_mounts,
+    },
+    pod::PodOptions,
+};
+
+/// Fields from a [`compose_spec::Service`] which will be [added](Service::add_to_pod()) to a
+/// [`Pod`]'s [`Container`]s, [`Volume`](k8s_openapi::api::core::v1::Volume)s, and annotations.
 #[allow(clippy::struct_excessive_bools)]
 pub(super) struct Service {
     unsupported: Unsupported,
     name: Identifier,
+    pod_options: PodOptions,
+    podman_annotations: PodmanAnnotations,
     resources: ContainerResources,
     security_context: ContainerSecurityContext,
     command: Option<Command>,
+    configs: Vec<ShortOrLong<Identifier, ConfigOrSecret>>,
     entrypoint: Option<Command>,
+    env_file: Option<EnvFile>,
     environment: ListOrMap,
     healthcheck: Option<Healthcheck>,
     image: Option<Image>,
     ports: Ports,
     pull_policy: Option<PullPolicy>,
+    secrets: Vec<ShortOrLong<Identifier, ConfigOrSecret>>,
     stdin_open: bool,
     tmpfs: Option<ItemOrList<AbsolutePath>>,
     tty: bool,
@@ -85,7 +104,8 @@ impl Service {
             configs,
             container_name,
             credential_spec,
-            depends_on,
+            // converted by `dependencies::sort()`
+            depends_on: _,
             deploy,
             develop,
             device_cgroup_rules,
@@ -158,7 +178,6 @@ impl Service {
                 blkio_config,
                 cpu_count,
                 cpu_percent,
-                cpu_shares,
                 cpu_period,
                 cpu_quota,
                 cpu_rt_runtime,
@@ -166,27 +185,17 @@ impl Service {
                 cpuset,
                 cgroup,
                 cgroup_parent,
-                configs,
                 credential_spec,
-                depends_on,
                 deploy,
                 develop,
                 device_cgroup_rules,
                 devices,
-                dns,
-                dns_opt,
-                dns_search,
                 domain_name,
-                env_file,
                 expose,
                 extends,
                 annotations,
                 external_links,
-                extra_hosts,
                 group_add,
-                hostname,
-                init,
-                ipc,
                 uts,
                 isolation,
                 labels,
@@ -202,23 +211,37 @@ impl Service {
                 pids_limit,
                 platform,
                 profiles,
-                restart,
                 runtime,
                 scale,
-                secrets,
                 shm_size,
-                stop_grace_period,
                 stop_signal,
                 storage_opt,
-                sysctls,
-                ulimits,
-                userns_mode,
                 volumes_from,
                 extensions,
             },
             name: container_name.unwrap_or_else(|| name.clone()),
+            pod_options: PodOptions {
+                dns,
+                dns_opt,
+                dns_search,
+                extra_hosts,
+                hostname,
+                init,
+                ipc,
+                restart,
+                stop_grace_period,
+                sysctls,
+            },
+            podman_annotations: PodmanAnnotations {
+                init,
+                label: Vec::new(),
+                seccomp: None,
+                ulimits,
+                userns_mode,
+            },
             resources: ContainerResources {
                 cpus,
+                cpu_shares,
                 mem_limit,
                 mem_reservation,
             },
@@ -231,12 +254,15 @@ impl Service {
                 user,
             },
             command,
+            configs,
             entrypoint,
+            env_file,
             environment,
             healthcheck,
             image,
             ports,
             pull_policy,
+            secrets,
             stdin_open,
             tmpfs,
             tty,
@@ -245,24 +271,45 @@ impl Service {
         }
     }
 
-    /// Add the service to a [`PodSpec`]'s [`Container`]s and [`Volume`]s.
+    /// Add the service to a [`Pod`]'s [`Container`]s, or init containers if `init_container` is
+    /// `true`, [`Volume`]s, and annotations.
+    ///
+    /// Options without a Kubernetes equivalent are added as Podman-specific annotations. The
+    /// service's `configs` and `secrets` are mounted from the [`ConfigMap`] and
+    /// [`Secret`](k8s_openapi::api::core::v1::Secret) named in `objects`, and a [`ConfigMap`] made
+    /// from its `env_file`s is added to `objects`. Bind mounts are converted according to the
+    /// `bind_mode`. If `dropped` is [`Some`], unsupported fields are added to it instead of
+    /// returning an error.
     ///
     /// # Errors
     ///
-    /// Returns an error if an unsupported option was used or conversion of one of the fields fails.
-    pub(super) fn add_to_pod_spec(self, spec: &mut PodSpec) -> color_eyre::Result<()> {
+    /// Returns an error if an unsupported option was used and `dropped` is [`None`], or conversion
+    /// of one of the fields fails.
+    pub(super) fn add_to_pod(
+        self,
+        pod: &mut Pod,
+        objects: &mut Objects,
+        bind_mode: BindMode,
+        init_container: bool,
+        dropped: Option<&mut Vec<UnsupportedField>>,
+    ) -> color_eyre::Result<()> {
         let Self {
             unsupported,
             name,
+            pod_options,
+            mut podman_annotations,
             resources,
             security_context,
             command,
+            configs,
             entrypoint,
+            env_file,
             environment,
             healthcheck,
             image,
             ports,
             pull_policy,
+            secrets,
             stdin_open,
             tmpfs,
             tty,
@@ -270,52 +317,54 @@ impl Service {
             working_dir,
         } = self;
 
-        unsupported.ensure_empty()?;
+        ensure_empty_or_drop(unsupported.fields(), dropped)?;
 
-        let volume_mounts =
-            tmpfs_and_volumes_try_into_volume_mounts(tmpfs, volumes, &name, &mut spec.volumes)
-                // converting `tmpfs` always succeeds
-                .wrap_err("error converting `volumes`")?;
+        let spec = pod.spec.get_or_insert_with(PodSpec::default);
+        pod_options.add_to_pod_spec(spec)?;
 
-        spec.containers.push(Container {
+        let mut volume_mounts = tmpfs_and_volumes_try_into_volume_mounts(
+            tmpfs,
+            volumes,
+            &name,
+            bind_mode,
+            objects.project_directory,
+            &mut objects.bind_claims,
+            &mut spec.volumes,
+        )
+        // converting `tmpfs` always succeeds
+        .wrap_err("error converting `volumes`")?;
+        volume_mounts.extend(
+            configs_try_into_volume_mounts(configs, objects.config_map, &name, &mut spec.volumes)
+                .wrap_err("error converting `configs`")?,
+        );
+        volume_mounts.extend(
+            secrets_try_into_volume_mounts(secrets, objects.secret, &name, &mut spec.volumes)
+                .wrap_err("error converting `secrets`")?,
+        );
+
+        let env_from = objects
+            .add_env_file(env_file, &name)
+            .wrap_err("error converting `env_file`")?;
+
+        let (liveness_probe, startup_probe) =
+            healthcheck_try_into_probes(healthcheck, init_container)
+                .wrap_err("error converting `healthcheck`")?;
+
+        let container = Container {
             name: name.into(),
             resources: resources.into_resource_requirements(),
-            security_context: security_context.try_into_security_context()?,
+            security_context: security_context
+                .try_into_security_context(&mut podman_annotations)?,
             args: command
                 .map(command_try_into_vec)
                 .transpose()
                 .wrap_err("error converting `command` to `args`")?,
-            command: entrypoint.map(|entrypoint| match entrypoint {
-                Command::String(entrypoint) => {
-                    vec!["/bin/sh".to_owned(), "-c".to_owned(), entrypoint]
-                }
-                Command::List(entrypoint) => entrypoint,
-            }),
-            env: (!environment.is_empty())
-                .then(|| {
-                    environment.into_map().map(|environment| {
-                        environment
-                            .into_iter()
-                            .map(|(name, value)| EnvVar {
-                                name: name.into(),
-                                value: value.map(Into::into),
-                                value_from: None,
-                            })
-                            .collect()
-                    })
-                })
-                .transpose()
+            command: entrypoint.map(entrypoint_into_command),
+            env_from,
+            env: environment_try_into_env(environment)
                 .wrap_err("error converting `environment`")?,
-            liveness_probe: healthcheck
-                .and_then(|healthcheck| match healthcheck {
-                    Healthcheck::Command(command) => {
-                        Some(healthcheck_command_try_into_probe(command))
-                    }
-                    // container image healthchecks are disabled by default in k8s
-                    Healthcheck::Disable => None,
-                })
-                .transpose()
-                .wrap_err("error converting `healthcheck`")?,
+            liveness_probe,
+            startup_probe,
             image: Some(image.ok_or_eyre("`image` is required")?.into_inner()),
             ports: (!ports.is_empty())
                 .then(|| {
@@ -326,12 +375,7 @@ impl Service {
                 .transpose()
                 .wrap_err("error converting `ports`")?,
             image_pull_policy: pull_policy
-                .map(|pull_policy| match pull_policy {
-                    PullPolicy::Always => Ok("Always".to_owned()),
-                    PullPolicy::Never => Ok("Never".to_owned()),
-                    PullPolicy::Missing => Ok("IfNotPreset".to_owned()),
-                    PullPolicy::Build => Err(eyre!("`build` is not supported")),
-                })
+                .map(pull_policy_try_into_image_pull_policy)
                 .transpose()
                 .wrap_err("error converting `pull_policy`")?,
             stdin: stdin_open.then_some(true),
@@ -346,68 +390,290 @@ impl Service {
                 })
                 .transpose()?,
             ..Container::default()
-        });
+        };
+
+        podman_annotations.add_to_pod(&container.name, &mut pod.metadata.annotations)?;
+
+        if init_container {
+            spec.init_containers
+                .get_or_insert_with(Vec::new)
+                .push(container);
+        } else {
+            spec.containers.push(container);
+        }
 
         Ok(())
     }
 }
 
-/// Attempt to convert a [`compose_spec::Service`]'s [`healthcheck::Command`] into a Kubernetes
-/// [`Probe`] for use in the `liveness_probe` field of [`Container`].
+/// Convert a [`compose_spec::Service`]'s `entrypoint` into a [`Container`]'s `command`.
+///
+/// A string entrypoint is run with `/bin/sh -c`.
+fn entrypoint_into_command(entrypoint: Command) -> Vec<String> {
+    match entrypoint {
+        Command::String(entrypoint) => vec!["/bin/sh".to_owned(), "-c".to_owned(), entrypoint],
+        Command::List(entrypoint) => entrypoint,
+    }
+}
+
+/// Attempt to convert a [`compose_spec::Service`]'s [`PullPolicy`] into a [`Container`]'s
+/// `imagePullPolicy`.
+///
+/// # Errors
+///
+/// Returns an error if the pull policy is `build`, which is not supported.
+fn pull_policy_try_into_image_pull_policy(pull_policy: PullPolicy) -> color_eyre::Result<String> {
+    match pull_policy {
+        PullPolicy::Always => Ok("Always".to_owned()),
+        PullPolicy::Never => Ok("Never".to_owned()),
+        PullPolicy::Missing => Ok("IfNotPreset".to_owned()),
+        PullPolicy::Build => Err(eyre!("`build` is not supported")),
+    }
+}
+
+/// Kubernetes objects, other than the [`Pod`](k8s_openapi::api::core::v1::Pod), which
+/// [`Service`]s reference or add to.
+pub(super) struct Objects<'a> {
+    /// Name of the pod, used as a prefix for the names of created objects.
+    pub pod_name: &'a str,
+
+    /// Directory relative `env_file` paths are resolved from.
+    pub project_directory: &'a Path,
+
+    /// Name of the [`ConfigMap`] the compose `configs` are in.
+    pub config_map: &'a str,
+
+    /// Name of the [`Secret`](k8s_openapi::api::core::v1::Secret) the compose `secrets` are in.
+    pub secret: &'a str,
+
+    /// [`ConfigMap`]s created from services' `env_file`s.
+    pub env_config_maps: Vec<ConfigMap>,
+
+    /// Persistent volume claim names created from bind mount sources, and the source each was
+    /// created from.
+    pub bind_claims: HashMap<String, PathBuf>,
+}
+
+/// Attempt to convert a [`compose_spec::Service`]'s `environment` into [`EnvVar`]s for a
+/// [`Container`].
+///
+/// Returns [`None`] if the `environment` is empty.
+///
+/// # Errors
+///
+/// Returns an error if a variable name is not a valid map key.
+fn environment_try_into_env(
+    environment: ListOrMap,
+) -> Result<Option<Vec<EnvVar>>, InvalidMapKeyError> {
+    (!environment.is_empty())
+        .then(|| {
+            environment.into_map().map(|environment| {
+                environment
+                    .into_iter()
+                    .map(|(name, value)| EnvVar {
+                        name: name.into(),
+                        value: value.map(Into::into),
+                        value_from: None,
+                    })
+                    .collect()
+            })
+        })
+        .transpose()
+}
+
+impl Objects<'_> {
+    /// Attempt to add a [`ConfigMap`] made from a [`compose_spec::Service`]'s [`EnvFile`], if
+    /// [`Some`], returning the `env_from` for the service's [`Container`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an environment file could not be read or parsed.
+    fn add_env_file(
+        &mut self,
+        env_file: Option<EnvFile>,
+        container_name: &Identifier,
+    ) -> color_eyre::Result<Option<Vec<EnvFromSource>>> {
+        let Some(config_map) = env_file
+            .map(|env_file| {
+                env_file_try_into_config_map(
+                    env_file,
+                    self.project_directory,
+                    format!("{}-{container_name}-env", self.pod_name),
+                )
+            })
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+
+        let env_from = EnvFromSource {
+            config_map_ref: Some(ConfigMapEnvSource {
+                name: config_map.metadata.name.clone(),
+                optional: None,
+            }),
+            prefix: None,
+            secret_ref: None,
+        };
+        self.env_config_maps.push(config_map);
+
+        Ok(Some(vec![env_from]))
+    }
+}
+
+/// Attempt to read a [`compose_spec::Service`]'s [`EnvFile`] into a [`ConfigMap`] with the given
+/// `name`, for use in a [`Container`]'s `env_from`.
+///
+/// Relative paths are resolved from the `project_directory`. Variables from later files override
+/// those from earlier files. Files which are not required and do not exist are skipped, returns
+/// [`None`] if no files are left.
 ///
 /// # Errors
 ///
-/// Returns an error if extensions are present or there was an error converting one of the
-/// [`Duration`]s into seconds.
-fn healthcheck_command_try_into_probe(
+/// Returns an error if a file could not be read or parsed.
+fn env_file_try_into_config_map(
+    env_file: EnvFile,
+    project_directory: &Path,
+    name: String,
+) -> color_eyre::Result<Option<ConfigMap>> {
+    let paths: Vec<PathBuf> = env_file
+        .into_list()
+        .into_iter()
+        .filter_map(|env_file| {
+            let env_file::Config { path, required } = env_file.into_long();
+            let path = project_directory.join(path);
+            (required || path.exists()).then_some(path)
+        })
+        .collect();
+
+    if paths.is_empty() {
+        return Ok(None);
+    }
+
+    let data = Variables::from_env_files(&paths, project_directory)?
+        .into_values()
+        .into_iter()
+        .collect();
+
+    Ok(Some(ConfigMap {
+        metadata: ObjectMeta {
+            name: Some(name),
+            ..ObjectMeta::default()
+        },
+        data: Some(data),
+        ..ConfigMap::default()
+    }))
+}
+
+/// Attempt to convert a [`compose_spec::Service`]'s [`Healthcheck`] into Kubernetes liveness and
+/// startup [`Probe`]s.
+///
+/// # Errors
+///
+/// Returns an error if the service is an init container and has a healthcheck, or the
+/// healthcheck could not be converted.
+fn healthcheck_try_into_probes(
+    healthcheck: Option<Healthcheck>,
+    init_container: bool,
+) -> color_eyre::Result<(Option<Probe>, Option<Probe>)> {
+    match healthcheck {
+        Some(Healthcheck::Command(command)) => {
+            ensure!(
+                !init_container,
+                "healthchecks are not supported for init containers"
+            );
+            let (liveness, startup) = healthcheck_command_try_into_probes(command)?;
+            Ok((Some(liveness), startup))
+        }
+        // container image healthchecks are disabled by default in k8s
+        Some(Healthcheck::Disable) | None => Ok((None, None)),
+    }
+}
+
+/// Attempt to convert a [`compose_spec::Service`]'s [`healthcheck::Command`] into Kubernetes
+/// [`Probe`]s for use in the `liveness_probe` and `startup_probe` fields of [`Container`].
+///
+/// If `start_period` is set, a startup probe is returned which runs the healthcheck every
+/// `start_interval` until the start period is over. The liveness probe only starts after the
+/// startup probe succeeds.
+///
+/// # Errors
+///
+/// Returns an error if extensions are present, `test` is not set, or there was an error converting
+/// one of the [`Duration`]s into seconds.
+fn healthcheck_command_try_into_probes(
     healthcheck::Command {
         test,
         interval,
         timeout,
         retries,
         start_period,
-        // k8s doesn't run probes during startup
-        start_interval: _,
+        start_interval,
         extensions,
     }: healthcheck::Command,
-) -> color_eyre::Result<Probe> {
+) -> color_eyre::Result<(Probe, Option<Probe>)> {
     ensure!(
         extensions.is_empty(),
         "compose extensions are not supported"
     );
 
-    Ok(Probe {
-        exec: test.map(|test| ExecAction {
-            command: Some(match test {
-                Test::Command(test) => test,
-                Test::ShellCommand(test) => vec!["/bin/sh".to_owned(), "-c".to_owned(), test],
-            }),
+    let test = test
+        .ok_or_eyre("`test` is required, Kubernetes probes cannot use the image's healthcheck")?;
+    let exec = Some(ExecAction {
+        command: Some(match test {
+            Test::Command(test) => test,
+            Test::ShellCommand(test) => vec!["/bin/sh".to_owned(), "-c".to_owned(), test],
         }),
+    });
+    let timeout_seconds = Some(
+        timeout
+            .map(duration_round_seconds)
+            .map(TryInto::try_into)
+            .transpose()
+            .wrap_err("error converting `timeout`")?
+            // default timeout for compose is 30 seconds, for k8s its 1 second
+            .unwrap_or(30),
+    );
+
+    let startup = start_period
+        .map(|start_period| {
+            // default start interval for compose is 5 seconds
+            let start_interval = start_interval.map_or(5, duration_round_seconds);
+            let failure_threshold = duration_round_seconds(start_period).div_ceil(start_interval);
+            color_eyre::Result::<_>::Ok(Probe {
+                exec: exec.clone(),
+                period_seconds: Some(
+                    start_interval
+                        .try_into()
+                        .wrap_err("error converting `start_interval`")?,
+                ),
+                timeout_seconds,
+                failure_threshold: Some(
+                    failure_threshold
+                        .try_into()
+                        .wrap_err("error converting `start_period`")?,
+                ),
+                ..Probe::default()
+            })
+        })
+        .transpose()?;
+
+    let liveness = Probe {
+        exec,
         period_seconds: interval
             .map(duration_round_seconds)
             .map(TryInto::try_into)
             .transpose()
             .wrap_err("error converting `interval`")?,
-        timeout_seconds: Some(
-            timeout
-                .map(duration_round_seconds)
-                .map(TryInto::try_into)
-                .transpose()
-                .wrap_err("error converting `timeout`")?
-                // default timeout for compose is 30 seconds, for k8s its 1 second
-                .unwrap_or(30),
-        ),
+        timeout_seconds,
         failure_threshold: retries
             .map(TryInto::try_into)
             .transpose()
             .wrap_err("error converting `retries`")?,
-        initial_delay_seconds: start_period
-            .map(duration_round_seconds)
-            .map(TryInto::try_into)
-            .transpose()
-            .wrap_err("error converting `start_period`")?,
         ..Probe::default()
-    })
+    };
+
+    Ok((liveness, startup))
 }
 
 /// Round a [`Duration`] to the nearest whole seconds with a minimum of 1 second.
@@ -482,6 +748,7 @@ fn port_try_into_container_port(
 /// [`ResourceRequirements`].
 struct ContainerResources {
     cpus: Option<Cpus>,
+    cpu_shares: Option<u64>,
     mem_limit: Option<ByteValue>,
     mem_reservation: Option<ByteValue>,
 }
@@ -489,10 +756,13 @@ struct ContainerResources {
 impl ContainerResources {
     /// Convert into [`ResourceRequirements`] for a Kubernetes [`Container`].
     ///
+    /// `cpu_shares` are converted into a CPU request, where one CPU is 1024 shares.
+    ///
     /// Returns [`None`] if no resource options are set.
     fn into_resource_requirements(self) -> Option<ResourceRequirements> {
         let Self {
             cpus,
+            cpu_shares,
             mem_limit,
             mem_reservation,
         } = self;
@@ -515,6 +785,16 @@ impl ContainerResources {
                 .insert("memory".to_owned(), Quantity(mem_limit.to_string()));
         }
 
+        if let Some(cpu_shares) = cpu_shares {
+            #[allow(clippy::cast_precision_loss)]
+            let millicpus = (cpu_shares as f64 * 1000.0 / 1024.0).round();
+            resources
+                .get_or_insert_with(ResourceRequirements::default)
+                .requests
+                .get_or_insert_with(BTreeMap::default)
+                .insert("cpu".to_owned(), Quantity(format!("{millicpus}m")));
+        }
+
         if let Some(mem_reservation) = mem_reservation {
             resources
                 .get_or_insert_with(ResourceRequirements::default)
@@ -541,12 +821,16 @@ struct ContainerSecurityContext {
 impl ContainerSecurityContext {
     /// Attempt to convert into [`SecurityContext`] for a Kubernetes [`Container`].
     ///
-    /// Returns [`None`] if no security context options are set.
+    /// Returns [`None`] if no security context options are set. `security_opt` label and seccomp
+    /// options not supported by the [`SecurityContext`] are added to the `podman_annotations`.
     ///
     /// # Errors
     ///
     /// Returns an error if the conversion of one of the fields fails.
-    fn try_into_security_context(self) -> color_eyre::Result<Option<SecurityContext>> {
+    fn try_into_security_context(
+        self,
+        podman_annotations: &mut PodmanAnnotations,
+    ) -> color_eyre::Result<Option<SecurityContext>> {
         let Self {
             cap_add,
             cap_drop,
@@ -587,11 +871,14 @@ impl ContainerSecurityContext {
         }
 
         if !security_opt.is_empty() {
-            let se_linux_options = security_opt_try_into_selinux_options(security_opt)
-                .wrap_err("error converting `security_opt`")?;
-            security_context
-                .get_or_insert_with(SecurityContext::default)
-                .se_linux_options = Some(se_linux_options);
+            let se_linux_options =
+                security_opt_try_into_selinux_options(security_opt, podman_annotations)
+                    .wrap_err("error converting `security_opt`")?;
+            if se_linux_options != SELinuxOptions::default() {
+                security_context
+                    .get_or_insert_with(SecurityContext::default)
+                    .se_linux_options = Some(se_linux_options);
+            }
         }
 
         if let Some(user) = user {
@@ -610,11 +897,15 @@ impl ContainerSecurityContext {
 
 /// Attempt to convert a [`compose_spec::Service`]'s `security_opt` field into [`SELinuxOptions`].
 ///
+/// The `label:filetype`, `label:disable`, `label:nested`, and `seccomp` options are added to the
+/// `podman_annotations` instead.
+///
 /// # Errors
 ///
 /// Returns an error if an unknown or unsupported security opt is given.
 fn security_opt_try_into_selinux_options(
     security_opt: IndexSet<String>,
+    podman_annotations: &mut PodmanAnnotations,
 ) -> color_eyre::Result<SELinuxOptions> {
     security_opt.into_iter().try_fold(
         SELinuxOptions::default(),
@@ -646,19 +937,33 @@ fn security_opt_try_into_selinux_options(
                         selinux_options.level = Some(level);
                         Ok(selinux_options)
                     }
-                    LabelOpt::Filetype(_) => {
-                        Err(eyre!("`label:filetype` security_opt is not supported"))
+                    LabelOpt::Filetype(filetype) => {
+                        podman_annotations
+                            .label
+                            .push(format!("filetype:{filetype}"));
+                        Ok(selinux_options)
                     }
                     LabelOpt::Disable => {
-                        Err(eyre!("`label:disable` security_opt is not supported"))
+                        podman_annotations.label.push("disable".to_owned());
+                        Ok(selinux_options)
+                    }
+                    LabelOpt::Nested => {
+                        podman_annotations.label.push("nested".to_owned());
+                        Ok(selinux_options)
                     }
-                    LabelOpt::Nested => Err(eyre!("`label:nested` security_opt is not supported")),
                 },
                 SecurityOpt::Mask(_) => Err(eyre!("`mask` security_opt is not supported")),
                 SecurityOpt::NoNewPrivileges => {
                     Err(eyre!("`no-new-privileges` security_opt is not supported"))
                 }
-                SecurityOpt::Seccomp(_) => Err(eyre!("`seccomp` security_opt is not supported")),
+                SecurityOpt::Seccomp(profile) => {
+                    let profile = profile
+                        .into_os_string()
+                        .into_string()
+                        .map_err(|_| eyre!("`seccomp` profile must contain only valid UTF-8"))?;
+                    podman_annotations.seccomp = Some(profile);
+                    Ok(selinux_options)
+                }
                 SecurityOpt::ProcOpts(_) => Err(eyre!("`proc-opts` security_opt is not supported")),
                 SecurityOpt::Unmask(_) => Err(eyre!("`unmask` security_opt is not supported")),
             }
@@ -674,7 +979,6 @@ struct Unsupported {
     blkio_config: Option<BlkioConfig>,
     cpu_count: Option<u64>,
     cpu_percent: Option<Percent>,
-    cpu_shares: Option<u64>,
     cpu_period: Option<Duration>,
     cpu_quota: Option<Duration>,
     cpu_rt_runtime: Option<Duration>,
@@ -682,27 +986,17 @@ struct Unsupported {
     cpuset: CpuSet,
     cgroup: Option<Cgroup>,
     cgroup_parent: Option<String>,
-    configs: Vec<ShortOrLong<Identifier, ConfigOrSecret>>,
     credential_spec: Option<CredentialSpec>,
-    depends_on: DependsOn,
     deploy: Option<Deploy>,
     develop: Option<Develop>,
     device_cgroup_rules: IndexSet<CgroupRule>,
     devices: IndexSet<Device>,
-    dns: Option<ItemOrList<IpAddr>>,
-    dns_opt: IndexSet<String>,
-    dns_search: Option<ItemOrList<Hostname>>,
     domain_name: Option<Hostname>,
-    env_file: Option<EnvFile>,
     expose: IndexSet<Expose>,
     extends: Option<Extends>,
     annotations: ListOrMap,
     external_links: IndexSet<Link>,
-    extra_hosts: IndexMap<Hostname, IpAddr>,
     group_add: IndexSet<UserOrGroup>,
-    hostname: Option<Hostname>,
-    init: bool,
-    ipc: Option<Ipc>,
     uts: Option<Uts>,
     isolation: Option<String>,
     labels: ListOrMap,
@@ -718,36 +1012,25 @@ struct Unsupported {
     pids_limit: Option<Limit<u32>>,
     platform: Option<Platform>,
     profiles: IndexSet<Identifier>,
-    restart: Option<Restart>,
     runtime: Option<String>,
     scale: Option<u64>,
-    secrets: Vec<ShortOrLong<Identifier, ConfigOrSecret>>,
     shm_size: Option<ByteValue>,
-    stop_grace_period: Option<Duration>,
     stop_signal: Option<String>,
     storage_opt: Map,
-    sysctls: ListOrMap,
-    ulimits: Ulimits,
-    userns_mode: Option<String>,
     volumes_from: IndexSet<VolumesFrom>,
     extensions: Extensions,
 }
 
 impl Unsupported {
-    /// Ensure that all unsupported fields are [`None`] or empty.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if a field is not empty.
+    /// Unsupported fields which are not [`None`] or empty.
     #[allow(clippy::too_many_lines)]
-    fn ensure_empty(&self) -> color_eyre::Result<()> {
+    fn fields(&self) -> Vec<UnsupportedField> {
         let Self {
             attach,
             build,
             blkio_config,
             cpu_count,
             cpu_percent,
-            cpu_shares,
             cpu_period,
             cpu_quota,
             cpu_rt_runtime,
@@ -755,27 +1038,17 @@ impl Unsupported {
             cpuset,
             cgroup,
             cgroup_parent,
-            configs,
             credential_spec,
-            depends_on,
             deploy,
             develop,
             device_cgroup_rules,
             devices,
-            dns,
-            dns_opt,
-            dns_search,
             domain_name,
-            env_file,
             expose,
             extends,
             annotations,
             external_links,
-            extra_hosts,
             group_add,
-            hostname,
-            init,
-            ipc,
             uts,
             isolation,
             labels,
@@ -791,17 +1064,11 @@ impl Unsupported {
             pids_limit,
             platform,
             profiles,
-            restart,
             runtime,
             scale,
-            secrets,
             shm_size,
-            stop_grace_period,
             stop_signal,
             storage_opt,
-            sysctls,
-            ulimits,
-            userns_mode,
             volumes_from,
             extensions,
         } = self;
@@ -812,7 +1079,6 @@ impl Unsupported {
             ("blkio_config", blkio_config.is_none()),
             ("cpu_count", cpu_count.is_none()),
             ("cpu_percent", cpu_percent.is_none()),
-            ("cpu_shares", cpu_shares.is_none()),
             ("cpu_period", cpu_period.is_none()),
             ("cpu_quota", cpu_quota.is_none()),
             ("cpu_rt_runtime", cpu_rt_runtime.is_none()),
@@ -820,14 +1086,11 @@ impl Unsupported {
             ("cpuset", cpuset.is_empty()),
             ("cgroup", cgroup.is_none()),
             ("cgroup_parent", cgroup_parent.is_none()),
-            ("configs", configs.is_empty()),
             ("credential_spec", credential_spec.is_none()),
-            ("depends_on", depends_on_is_empty(depends_on)),
             ("deploy", deploy.is_none()),
             ("develop", develop.is_none()),
             ("device_cgroup_rules", device_cgroup_rules.is_empty()),
             ("domainname", domain_name.is_none()),
-            ("env_file", env_file.is_none()),
             ("expose", expose.is_empty()),
             ("extends", extends.is_none()),
             ("external_links", external_links.is_empty()),
@@ -854,76 +1117,119 @@ impl Unsupported {
             ("profiles", profiles.is_empty()),
             ("runtime", runtime.is_none()),
             ("scale", scale.is_none()),
-            ("secrets", secrets.is_empty()),
             ("shm_size", shm_size.is_none()),
             ("stop_signal", stop_signal.is_none()),
             ("storage_opt", storage_opt.is_empty()),
-            ("ulimits", ulimits.is_empty()),
-            ("userns_mode", userns_mode.is_none()),
             ("volumes_from", volumes_from.is_empty()),
         ];
-        for (option, not_present) in unsupported_options {
-            ensure!(
-                not_present,
-                "`{option}` is not supported for Kubernetes pod containers"
-            );
-        }
-
-        let pod_spec_options = [
-            ("dns", dns.is_none()),
-            ("dns_opt", dns_opt.is_empty()),
-            ("dns_search", dns_search.is_none()),
-            ("extra_hosts", extra_hosts.is_empty()),
-            ("hostname", hostname.is_none()),
-            ("init", !init),
-            ("ipc", ipc.is_none()),
-            ("pid", pid.is_none()),
-            ("restart", restart.is_none()),
-            ("stop_grace_period", stop_grace_period.is_none()),
-            ("sysctls", sysctls.is_empty()),
-        ];
-        for (option, not_present) in pod_spec_options {
-            if !not_present {
-                return Err(eyre!(
-                    "Kubernetes pods do not support per container `{option}` options",
+        let mut fields: Vec<_> = unsupported_options
+            .into_iter()
+            .filter(|(_, not_present)| !not_present)
+            .map(|(option, _)| {
+                UnsupportedField::new(
+                    option,
+                    format!("`{option}` is not supported for Kubernetes pod containers"),
                 )
-                .suggestion("try using setting the option in the pod spec instead"));
-            }
-        }
+            })
+            .collect();
+
+        let pod_spec_options = [("pid", pid.is_none())];
+        fields.extend(
+            pod_spec_options
+                .into_iter()
+                .filter(|(_, not_present)| !not_present)
+                .map(|(option, _)| {
+                    UnsupportedField::new(
+                        option,
+                        format!("Kubernetes pods do not support per container `{option}` options"),
+                    )
+                    .with_suggestion("try using setting the option in the pod spec instead")
+                }),
+        );
 
         let pod_metadata_options = [
             ("annotations", annotations.is_empty()),
             ("labels", labels.is_empty()),
         ];
-        for (option, not_present) in pod_metadata_options {
-            if !not_present {
-                return Err(eyre!(
-                    "Kubernetes pods do not support per container `{option}` options",
-                )
-                .suggestion("try using setting the option in the pod metadata instead"));
-            }
-        }
+        fields.extend(
+            pod_metadata_options
+                .into_iter()
+                .filter(|(_, not_present)| !not_present)
+                .map(|(option, _)| {
+                    UnsupportedField::new(
+                        option,
+                        format!("Kubernetes pods do not support per container `{option}` options"),
+                    )
+                    .with_suggestion("try using setting the option in the pod metadata instead")
+                }),
+        );
 
         if !devices.is_empty() {
-            return Err(
-                eyre!("Kubernetes pod containers do not directly support devices")
-                    .suggestion("try using a bind mount instead"),
+            fields.push(
+                UnsupportedField::new(
+                    "devices",
+                    "Kubernetes pod containers do not directly support devices",
+                )
+                .with_suggestion("try using a bind mount instead"),
             );
         };
 
-        ensure!(
-            extensions.is_empty(),
-            "compose extensions are not supported"
-        );
+        if !extensions.is_empty() {
+            fields.push(UnsupportedField::new(
+                "extensions",
+                "compose extensions are not supported",
+            ));
+        }
 
-        Ok(())
+        fields
     }
 }
 
-/// Return `true` if the [`DependsOn`] is empty.
-fn depends_on_is_empty(depends_on: &DependsOn) -> bool {
-    match depends_on {
-        ShortOrLong::Short(depends_on) => depends_on.is_empty(),
-        ShortOrLong::Long(depends_on) => depends_on.is_empty(),
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn healthcheck_probes() {
+        let command = healthcheck::Command {
+            test: Some(Test::Command(vec!["check".to_owned()])),
+            interval: Some(Duration::from_secs(10)),
+            timeout: None,
+            retries: None,
+            start_period: Some(Duration::from_secs(32)),
+            start_interval: Some(Duration::from_secs(3)),
+            extensions: Extensions::default(),
+        };
+        let (liveness, startup) = healthcheck_command_try_into_probes(command.clone()).unwrap();
+
+        assert_eq!(liveness.period_seconds, Some(10));
+        assert_eq!(liveness.initial_delay_seconds, None);
+        let startup = startup.unwrap();
+        assert_eq!(startup.exec, liveness.exec);
+        assert_eq!(startup.period_seconds, Some(3));
+        assert_eq!(startup.failure_threshold, Some(11));
+
+        let command = healthcheck::Command {
+            test: None,
+            ..command
+        };
+        assert!(healthcheck_command_try_into_probes(command).is_err());
+    }
+
+    #[test]
+    fn cpu_request() {
+        let resources = ContainerResources {
+            cpus: None,
+            cpu_shares: Some(512),
+            mem_limit: None,
+            mem_reservation: None,
+        };
+        let requests = resources
+            .into_resource_requirements()
+            .unwrap()
+            .requests
+            .unwrap();
+        assert_eq!(requests.get("cpu"), Some(&Quantity("500m".to_owned())));
     }
 }
@@ -0,0 +1,169 @@
+//! [`PodmanAnnotations`] are fields of a [`compose_spec::Service`] without a Kubernetes equivalent,
+//! which are converted into the Podman-specific annotations read by `podman kube play`.
+
+use std::collections::BTreeMap;
+
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Section,
+};
+use compose_spec::service::Ulimits;
+
+use crate::cli::container::ulimit_try_into_short;
+
+/// Prefix of the annotation keys `podman kube play` reads.
+const PREFIX: &str = "io.podman.annotations";
+
+/// Fields from a [`compose_spec::Service`] which are added to a Kubernetes pod's annotations.
+#[derive(Debug, Default)]
+pub(super) struct PodmanAnnotations {
+    /// Run an init process in the container.
+    pub init: bool,
+
+    /// SELinux label options which are not supported by Kubernetes' `seLinuxOptions`.
+    pub label: Vec<String>,
+
+    /// Seccomp profile of the container.
+    pub seccomp: Option<String>,
+
+    pub ulimits: Ulimits,
+
+    /// User namespace of the pod.
+    pub userns_mode: Option<String>,
+}
+
+impl PodmanAnnotations {
+    /// Add the annotations to a Kubernetes pod's `annotations`.
+    ///
+    /// Container annotation keys are suffixed with `/{container_name}`. `userns_mode` applies to
+    /// the whole pod.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `userns_mode` conflicts with the value set by another service, or a
+    /// ulimit could not be converted.
+    pub(super) fn add_to_pod(
+        self,
+        container_name: &str,
+        annotations: &mut Option<BTreeMap<String, String>>,
+    ) -> color_eyre::Result<()> {
+        let Self {
+            init,
+            label,
+            seccomp,
+            ulimits,
+            userns_mode,
+        } = self;
+
+        let mut container = BTreeMap::new();
+
+        if init {
+            container.insert("init", "TRUE".to_owned());
+        }
+
+        if !label.is_empty() {
+            container.insert("label", label.join(","));
+        }
+
+        if let Some(seccomp) = seccomp {
+            container.insert("seccomp", seccomp);
+        }
+
+        if !ulimits.is_empty() {
+            let ulimits = ulimits
+                .into_iter()
+                .map(ulimit_try_into_short)
+                .collect::<Result<Vec<_>, _>>()
+                .wrap_err("error converting `ulimits`")?;
+            container.insert("ulimit", ulimits.join(","));
+        }
+
+        if container.is_empty() && userns_mode.is_none() {
+            return Ok(());
+        }
+        let annotations = annotations.get_or_insert_with(BTreeMap::new);
+
+        if let Some(userns_mode) = userns_mode {
+            let key = format!("{PREFIX}.userns");
+            match annotations.get(&key) {
+                Some(userns) if *userns != userns_mode => {
+                    return Err(eyre!(
+                        "`userns_mode` conflicts with the value `{userns}` set by another service"
+                    )
+                    .suggestion(
+                        "all containers in a Kubernetes pod share its user namespace, \
+                            use the same `userns_mode` for each service",
+                    ));
+                }
+                _ => {
+                    annotations.insert(key, userns_mode);
+                }
+            }
+        }
+
+        annotations.extend(
+            container
+                .into_iter()
+                .map(|(key, value)| (format!("{PREFIX}.{key}/{container_name}"), value)),
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use compose_spec::{service::Resource, ShortOrLong};
+
+    use super::*;
+
+    #[test]
+    fn annotations() {
+        let mut annotations = None;
+        PodmanAnnotations {
+            init: true,
+            label: vec!["disable".to_owned()],
+            seccomp: Some("unconfined".to_owned()),
+            ulimits: [(Resource::new("nofile").unwrap(), ShortOrLong::Short(1024))].into(),
+            userns_mode: Some("keep-id".to_owned()),
+        }
+        .add_to_pod("web", &mut annotations)
+        .unwrap();
+        PodmanAnnotations {
+            userns_mode: Some("keep-id".to_owned()),
+            ..PodmanAnnotations::default()
+        }
+        .add_to_pod("db", &mut annotations)
+        .unwrap();
+
+        let annotations = annotations.unwrap();
+        let annotations: Vec<(&str, &str)> = annotations
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            annotations,
+            [
+                ("io.podman.annotations.init/web", "TRUE"),
+                ("io.podman.annotations.label/web", "disable"),
+                ("io.podman.annotations.seccomp/web", "unconfined"),
+                ("io.podman.annotations.ulimit/web", "nofile=1024"),
+                ("io.podman.annotations.userns", "keep-id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn userns_conflict() {
+        let mut annotations = None;
+        let userns = |userns_mode: &str| PodmanAnnotations {
+            userns_mode: Some(userns_mode.to_owned()),
+            ..PodmanAnnotations::default()
+        };
+        userns("keep-id")
+            .add_to_pod("web", &mut annotations)
+            .unwrap();
+        assert!(userns("auto").add_to_pod("db", &mut annotations).is_err());
+    }
+}
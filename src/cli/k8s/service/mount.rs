@@ -2,30 +2,43 @@
 //! [`VolumeMount`] and [`Volume`] for a [`Container`](k8s_openapi::api::core::v1::Container) and
 //! its [`PodSpec`](k8s_openapi::api::core::v1::PodSpec).
 
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
 use color_eyre::eyre::{ensure, eyre, WrapErr};
 use compose_spec::{
     service::{
         volumes::{
             self,
-            mount::{self, Bind, BindOptions, Common, Tmpfs, TmpfsOptions, VolumeOptions},
-            Mount,
+            mount::{
+                self, Bind, BindOptions, BindPropagation, Common, Tmpfs, TmpfsOptions,
+                VolumeOptions,
+            },
+            HostPath, Mount,
         },
-        AbsolutePath, Volumes,
+        AbsolutePath, ConfigOrSecret, Volumes,
     },
-    Identifier, ItemOrList,
+    Identifier, ItemOrList, ShortOrLong,
 };
 use k8s_openapi::{
     api::core::v1::{
-        EmptyDirVolumeSource, HostPathVolumeSource, PersistentVolumeClaimVolumeSource, Volume,
-        VolumeMount,
+        ConfigMapVolumeSource, EmptyDirVolumeSource, HostPathVolumeSource, KeyToPath,
+        PersistentVolumeClaimVolumeSource, SecretVolumeSource, Volume, VolumeMount,
     },
     apimachinery::pkg::api::resource::Quantity,
 };
 
+use super::BindMode;
+
 /// Attempt to convert the `tmpfs` and `volumes` fields from a [`compose_spec::Service`] into
 /// [`VolumeMount`]s.
 ///
-/// The corresponding [`Volume`]s are added to `pod_volumes`.
+/// Bind mounts are converted according to the `bind_mode`, relative bind sources are resolved from
+/// the `project_directory`. Persistent volume claims created from bind sources are added to
+/// `bind_claims`. The corresponding [`Volume`]s are added to `pod_volumes`.
 ///
 /// # Errors
 ///
@@ -34,6 +47,9 @@ pub(super) fn tmpfs_and_volumes_try_into_volume_mounts(
     tmpfs: Option<ItemOrList<AbsolutePath>>,
     volumes: Volumes,
     container_name: &Identifier,
+    bind_mode: BindMode,
+    project_directory: &Path,
+    bind_claims: &mut HashMap<String, PathBuf>,
     pod_volumes: &mut Option<Vec<Volume>>,
 ) -> color_eyre::Result<Vec<VolumeMount>> {
     tmpfs
@@ -43,13 +59,178 @@ pub(super) fn tmpfs_and_volumes_try_into_volume_mounts(
         .map(Into::into)
         .chain(volumes::into_long_iter(volumes))
         .map(|mount| {
-            let (volume_mount, volume) = try_into_volume_mount(mount, container_name)?;
+            let (volume_mount, volume) = try_into_volume_mount(
+                mount,
+                container_name,
+                bind_mode,
+                project_directory,
+                bind_claims,
+            )?;
             pod_volumes.get_or_insert_with(Vec::new).push(volume);
             Ok(volume_mount)
         })
         .collect()
 }
 
+/// Attempt to convert the `configs` field from a [`compose_spec::Service`] into [`VolumeMount`]s.
+///
+/// Each config is mounted as a single file from the [`ConfigMap`](k8s_openapi::api::core::v1::ConfigMap)
+/// named `config_map`, where the config's source is the key. The corresponding [`Volume`]s are
+/// added to `pod_volumes`.
+///
+/// # Errors
+///
+/// Returns an error if the `uid` or `gid` is set or a config's target is not valid UTF-8.
+pub(super) fn configs_try_into_volume_mounts(
+    configs: Vec<ShortOrLong<Identifier, ConfigOrSecret>>,
+    config_map: &str,
+    container_name: &Identifier,
+    pod_volumes: &mut Option<Vec<Volume>>,
+) -> color_eyre::Result<Vec<VolumeMount>> {
+    configs
+        .into_iter()
+        .map(|config| {
+            config_or_secret_try_into_volume_mount(
+                config.into_long(),
+                ConfigOrSecretKind::Config { config_map },
+                container_name,
+                pod_volumes,
+            )
+        })
+        .collect()
+}
+
+/// Attempt to convert the `secrets` field from a [`compose_spec::Service`] into [`VolumeMount`]s.
+///
+/// Each secret is mounted as a single file from the [`Secret`](k8s_openapi::api::core::v1::Secret)
+/// named `secret`, where the secret's source is the key. Like with compose, secrets are mounted in
+/// `/run/secrets` unless their target is an absolute path. The corresponding [`Volume`]s are added
+/// to `pod_volumes`.
+///
+/// # Errors
+///
+/// Returns an error if the `uid` or `gid` is set or a secret's target is not valid UTF-8.
+pub(super) fn secrets_try_into_volume_mounts(
+    secrets: Vec<ShortOrLong<Identifier, ConfigOrSecret>>,
+    secret: &str,
+    container_name: &Identifier,
+    pod_volumes: &mut Option<Vec<Volume>>,
+) -> color_eyre::Result<Vec<VolumeMount>> {
+    secrets
+        .into_iter()
+        .map(|config_or_secret| {
+            config_or_secret_try_into_volume_mount(
+                config_or_secret.into_long(),
+                ConfigOrSecretKind::Secret { secret },
+                container_name,
+                pod_volumes,
+            )
+        })
+        .collect()
+}
+
+/// Whether a [`ConfigOrSecret`] is a config or a secret, and the name of the Kubernetes object it is
+/// mounted from.
+#[derive(Debug, Clone, Copy)]
+enum ConfigOrSecretKind<'a> {
+    Config { config_map: &'a str },
+    Secret { secret: &'a str },
+}
+
+impl ConfigOrSecretKind<'_> {
+    /// The compose field name, for use in error messages.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Config { .. } => "config",
+            Self::Secret { .. } => "secret",
+        }
+    }
+
+    /// Directory the [`ConfigOrSecret`] is mounted in if its `target` is not set or relative.
+    const fn default_directory(self) -> &'static str {
+        match self {
+            Self::Config { .. } => "/",
+            Self::Secret { .. } => "/run/secrets",
+        }
+    }
+}
+
+/// Attempt to convert a [`ConfigOrSecret`] into a [`VolumeMount`] of a single file from a
+/// Kubernetes object according to its `kind`.
+///
+/// The corresponding [`Volume`] is added to `pod_volumes`.
+///
+/// # Errors
+///
+/// Returns an error if the `uid` or `gid` is set or the target is not valid UTF-8.
+fn config_or_secret_try_into_volume_mount(
+    ConfigOrSecret {
+        source,
+        target,
+        uid,
+        gid,
+        mode,
+        extensions,
+    }: ConfigOrSecret,
+    kind: ConfigOrSecretKind,
+    container_name: &Identifier,
+    pod_volumes: &mut Option<Vec<Volume>>,
+) -> color_eyre::Result<VolumeMount> {
+    ensure!(
+        uid.is_none() && gid.is_none(),
+        "`uid` and `gid` are not supported, {} `{source}`",
+        kind.as_str(),
+    );
+    ensure!(
+        extensions.is_empty(),
+        "compose extensions are not supported"
+    );
+
+    let mount_path = Path::new(kind.default_directory())
+        .join(target.unwrap_or_else(|| source.as_str().into()))
+        .into_os_string()
+        .into_string()
+        .map_err(|_| eyre!("`target` must only contain valid UTF-8"))?;
+    let name = Source::Other { container_name }.into_volume_name(&mount_path);
+
+    let items = Some(vec![KeyToPath {
+        key: source.to_string(),
+        path: source.to_string(),
+        mode: mode
+            .map(TryInto::try_into)
+            .transpose()
+            .wrap_err("error converting `mode`")?,
+    }]);
+    pod_volumes.get_or_insert_with(Vec::new).push(match kind {
+        ConfigOrSecretKind::Config { config_map } => Volume {
+            name: name.clone(),
+            config_map: Some(ConfigMapVolumeSource {
+                name: Some(config_map.to_owned()),
+                items,
+                ..ConfigMapVolumeSource::default()
+            }),
+            ..Volume::default()
+        },
+        ConfigOrSecretKind::Secret { secret } => Volume {
+            name: name.clone(),
+            secret: Some(SecretVolumeSource {
+                secret_name: Some(secret.to_owned()),
+                items,
+                ..SecretVolumeSource::default()
+            }),
+            ..Volume::default()
+        },
+    });
+
+    Ok(VolumeMount {
+        mount_path,
+        name,
+        read_only: Some(true),
+        sub_path: Some(source.into()),
+        ..VolumeMount::default()
+    })
+}
+
 /// Attempt to convert a volume [`Mount`] from a [`compose_spec::Service`] into a [`VolumeMount`]
 /// and its corresponding [`Volume`].
 ///
@@ -59,12 +240,21 @@ pub(super) fn tmpfs_and_volumes_try_into_volume_mounts(
 fn try_into_volume_mount(
     mount: Mount,
     container_name: &Identifier,
+    bind_mode: BindMode,
+    project_directory: &Path,
+    bind_claims: &mut HashMap<String, PathBuf>,
 ) -> color_eyre::Result<(VolumeMount, Volume)> {
     match mount {
         Mount::Volume(volume) => volume_try_into_volume_mount(volume, container_name)
             .wrap_err("error converting `volume` type volume mount"),
-        Mount::Bind(bind) => bind_try_into_volume_mount(bind, container_name)
-            .wrap_err("error converting `bind` type volume mount"),
+        Mount::Bind(bind) => bind_try_into_volume_mount(
+            bind,
+            container_name,
+            bind_mode,
+            project_directory,
+            bind_claims,
+        )
+        .wrap_err("error converting `bind` type volume mount"),
         Mount::Tmpfs(tmpfs) => tmpfs_try_into_volume_mount(tmpfs, container_name)
             .wrap_err("error converting `tmpfs` type volume mount"),
         Mount::NamedPipe(_) => Err(eyre!("`npipe` volume mount type is not supported")),
@@ -117,9 +307,17 @@ fn volume_try_into_volume_mount(
 
 /// Attempt to convert a [`Bind`] volume [`Mount`] into a [`VolumeMount`].
 ///
+/// With [`BindMode::HostPath`], the bind's source, resolved from the `project_directory`, is a
+/// `hostPath` volume. Its `type` is `File` if the source is a file. Otherwise, it is
+/// `DirectoryOrCreate` if `create_host_path` is set, or `Directory` if not. With [`BindMode::Pvc`],
+/// the bind's source is a persistent volume claim named after the source, which is added to
+/// `bind_claims`. As a persistent volume claim cannot be a single file, file sources are still
+/// `hostPath` volumes.
+///
 /// # Errors
 ///
-/// Returns an error if an unsupported option is present.
+/// Returns an error if an unsupported option is present or the claim name of the source is already
+/// used by a different source.
 fn bind_try_into_volume_mount(
     Bind {
         source,
@@ -127,6 +325,9 @@ fn bind_try_into_volume_mount(
         common,
     }: Bind,
     container_name: &Identifier,
+    bind_mode: BindMode,
+    project_directory: &Path,
+    bind_claims: &mut HashMap<String, PathBuf>,
 ) -> color_eyre::Result<(VolumeMount, Volume)> {
     let BindOptions {
         propagation,
@@ -135,37 +336,116 @@ fn bind_try_into_volume_mount(
         extensions,
     } = bind.unwrap_or_default();
 
-    ensure!(propagation.is_none(), "`bind.propagation` is not supported");
-    ensure!(
-        create_host_path,
-        "`bind.create_host_path: false` is not supported"
-    );
     ensure!(
         extensions.is_empty(),
         "compose extensions are not supported"
     );
 
-    let mut volume_mount = common_try_into_volume_mount(common, Source::Other { container_name })?;
+    let path = crate::cli::absolute_clean_path(project_directory, source.as_path());
+    let type_ = host_path_type(&path, create_host_path);
+
+    let (mut volume_mount, volume) = match bind_mode {
+        BindMode::Pvc if type_.as_deref() != Some("File") => {
+            let claim_name = source_into_claim_name(&source, bind_claims)?;
+            let volume_mount = common_try_into_volume_mount(common, Source::Volume(claim_name))?;
+            let volume = Volume {
+                name: volume_mount.name.clone(),
+                persistent_volume_claim: Some(PersistentVolumeClaimVolumeSource {
+                    claim_name: volume_mount.name.clone(),
+                    read_only: None,
+                }),
+                ..Volume::default()
+            };
+            (volume_mount, volume)
+        }
+        BindMode::HostPath | BindMode::Pvc => {
+            let volume_mount =
+                common_try_into_volume_mount(common, Source::Other { container_name })?;
+            let volume = Volume {
+                name: volume_mount.name.clone(),
+                host_path: Some(HostPathVolumeSource {
+                    path: path
+                        .into_os_string()
+                        .into_string()
+                        .map_err(|_| eyre!("`source` must only contain valid UTF-8"))?,
+                    type_,
+                }),
+                ..Volume::default()
+            };
+            (volume_mount, volume)
+        }
+    };
+
+    volume_mount.mount_propagation = propagation
+        .map(|propagation| match propagation {
+            BindPropagation::Private | BindPropagation::RPrivate => Ok("None".to_owned()),
+            BindPropagation::Slave | BindPropagation::RSlave => Ok("HostToContainer".to_owned()),
+            BindPropagation::Shared | BindPropagation::RShared => Ok("Bidirectional".to_owned()),
+            BindPropagation::Unbindable | BindPropagation::RUnbindable => {
+                Err(eyre!("`bind.propagation: {propagation}` is not supported"))
+            }
+        })
+        .transpose()?;
+
     if let Some(selinux) = selinux {
         let mount_path = &mut volume_mount.mount_path;
         mount_path.push(':');
         mount_path.push(selinux.as_char());
     }
 
-    let volume = Volume {
-        name: volume_mount.name.clone(),
-        host_path: Some(HostPathVolumeSource {
-            path: source
-                .into_inner()
-                .into_os_string()
-                .into_string()
-                .map_err(|_| eyre!("`source` must only contain valid UTF-8"))?,
-            type_: None,
-        }),
-        ..Volume::default()
+    Ok((volume_mount, volume))
+}
+
+/// The `type` of a `hostPath` volume for the bind mount source at `path`.
+///
+/// Returns [`None`], so the type is not checked, if `path` exists but is not a file or directory,
+/// e.g. a socket or device.
+fn host_path_type(path: &Path, create_host_path: bool) -> Option<String> {
+    let type_ = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => "File",
+        Ok(metadata) if !metadata.is_dir() => return None,
+        _ if create_host_path => "DirectoryOrCreate",
+        _ => "Directory",
     };
+    Some(type_.to_owned())
+}
 
-    Ok((volume_mount, volume))
+/// Attempt to convert a bind mount's `source` into the name of a persistent volume claim.
+///
+/// The source's normal path components are converted into a DNS label, e.g. "./data/db" becomes
+/// "data-db". The name is added to `bind_claims`.
+///
+/// # Errors
+///
+/// Returns an error if the source has no normal path components, or the name is already used by a
+/// different source in `bind_claims`.
+fn source_into_claim_name(
+    source: &HostPath,
+    bind_claims: &mut HashMap<String, PathBuf>,
+) -> color_eyre::Result<Identifier> {
+    let source: PathBuf = source
+        .as_path()
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    let name = dns_label(&source.to_string_lossy());
+
+    let other = bind_claims
+        .entry(name.clone())
+        .or_insert_with(|| source.clone());
+    ensure!(
+        *other == source,
+        "bind sources `{}` and `{}` both have the persistent volume claim name `{name}`",
+        other.display(),
+        source.display(),
+    );
+
+    Identifier::new(name).wrap_err_with(|| {
+        format!(
+            "could not create a persistent volume claim name from bind source `{}`",
+            source.display()
+        )
+    })
 }
 
 /// Attempt to convert a [`Tmpfs`] volume [`Mount`] into a [`VolumeMount`].
@@ -253,13 +533,164 @@ impl<'a> Source<'a> {
     /// Convert source into a `name` for a [`Volume`].
     ///
     /// If [`Other`](Self::Other), the `container_name` is combined with the `mount_path` to create
-    /// the `name`.
+    /// the `name`, which is converted into a valid Kubernetes volume name.
     fn into_volume_name(self, mount_path: &str) -> String {
         match self {
             Self::Volume(volume) => volume.into(),
-            Self::Other { container_name } => {
-                format!("{container_name}{}", mount_path.replace(['/', '\\'], "-"))
-            }
+            Self::Other { container_name } => dns_label(&format!("{container_name}-{mount_path}")),
         }
     }
 }
+
+/// Convert `name` into a DNS-1123 label, as required for Kubernetes volume names.
+///
+/// The name is lowercased, and characters other than ASCII letters and digits are replaced with
+/// `-`. Repeated, leading, and trailing `-` are removed.
+fn dns_label(name: &str) -> String {
+    name.to_ascii_lowercase()
+        .split(|char: char| !char.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_mount_paths() {
+        let secrets: Vec<ShortOrLong<Identifier, ConfigOrSecret>> = serde_yaml::from_str(
+            "[one, {source: two, target: /etc/two}, {source: three, target: 3}]",
+        )
+        .unwrap();
+        let container_name = Identifier::new("web").unwrap();
+        let mut pod_volumes = None;
+        let volume_mounts =
+            secrets_try_into_volume_mounts(secrets, "secrets", &container_name, &mut pod_volumes)
+                .unwrap();
+
+        let mount_paths: Vec<_> = volume_mounts
+            .iter()
+            .map(|volume_mount| volume_mount.mount_path.as_str())
+            .collect();
+        assert_eq!(
+            mount_paths,
+            ["/run/secrets/one", "/etc/two", "/run/secrets/3"]
+        );
+
+        let secret_names: Vec<_> = pod_volumes
+            .unwrap()
+            .into_iter()
+            .map(|volume| volume.secret.unwrap().secret_name.unwrap())
+            .collect();
+        assert_eq!(secret_names, ["secrets"; 3]);
+
+        let secrets: Vec<ShortOrLong<Identifier, ConfigOrSecret>> =
+            serde_yaml::from_str("[{source: app, target: /etc/App.conf}]").unwrap();
+        let mut pod_volumes = None;
+        secrets_try_into_volume_mounts(secrets, "secrets", &container_name, &mut pod_volumes)
+            .unwrap();
+        let volume_names: Vec<_> = pod_volumes
+            .unwrap()
+            .into_iter()
+            .map(|volume| volume.name)
+            .collect();
+        assert_eq!(volume_names, ["web-etc-app-conf"]);
+    }
+
+    #[test]
+    fn bind_mounts() {
+        let volumes = || -> Volumes {
+            serde_yaml::from_str(
+                "- ./Cargo.toml:/Cargo.toml\n\
+                - {type: bind, source: ./src, target: /src, bind: {propagation: rshared}}\n\
+                - ./data/db:/db\n",
+            )
+            .unwrap()
+        };
+        let container_name = Identifier::new("web").unwrap();
+        let project_directory = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+        let mut pod_volumes = None;
+        let volume_mounts = tmpfs_and_volumes_try_into_volume_mounts(
+            None,
+            volumes(),
+            &container_name,
+            BindMode::HostPath,
+            project_directory,
+            &mut HashMap::new(),
+            &mut pod_volumes,
+        )
+        .unwrap();
+        let host_paths: Vec<_> = pod_volumes
+            .unwrap()
+            .into_iter()
+            .map(|volume| volume.host_path.unwrap())
+            .collect();
+        let types: Vec<_> = host_paths
+            .iter()
+            .map(|host_path| host_path.type_.as_deref().unwrap())
+            .collect();
+        assert_eq!(types, ["File", "Directory", "DirectoryOrCreate"]);
+        assert_eq!(
+            host_paths.first().unwrap().path,
+            project_directory.join("Cargo.toml").to_str().unwrap()
+        );
+        let propagation: Vec<_> = volume_mounts
+            .iter()
+            .map(|volume_mount| volume_mount.mount_propagation.as_deref())
+            .collect();
+        assert_eq!(propagation, [None, Some("Bidirectional"), None]);
+
+        let mut bind_claims = HashMap::new();
+        let mut pod_volumes = None;
+        tmpfs_and_volumes_try_into_volume_mounts(
+            None,
+            volumes(),
+            &container_name,
+            BindMode::Pvc,
+            project_directory,
+            &mut bind_claims,
+            &mut pod_volumes,
+        )
+        .unwrap();
+        let claim_names: Vec<_> = pod_volumes
+            .unwrap()
+            .into_iter()
+            .map(|volume| volume.persistent_volume_claim.map(|claim| claim.claim_name))
+            .collect();
+        assert_eq!(
+            claim_names,
+            [None, Some("src".to_owned()), Some("data-db".to_owned())]
+        );
+
+        let volumes: Volumes =
+            serde_yaml::from_str("[./Data.DB:/a, ./data/db:/b, ./data/db/:/c]").unwrap();
+        let mut pod_volumes = None;
+        let error = tmpfs_and_volumes_try_into_volume_mounts(
+            None,
+            volumes,
+            &container_name,
+            BindMode::Pvc,
+            project_directory,
+            &mut bind_claims,
+            &mut pod_volumes,
+        )
+        .unwrap_err();
+        assert!(format!("{error:?}").contains("`data/db` and `Data.DB`"));
+
+        let volumes: Volumes = serde_yaml::from_str("[./data/db/:/c]").unwrap();
+        tmpfs_and_volumes_try_into_volume_mounts(
+            None,
+            volumes,
+            &container_name,
+            BindMode::Pvc,
+            project_directory,
+            &mut bind_claims,
+            &mut pod_volumes,
+        )
+        .unwrap();
+    }
+}
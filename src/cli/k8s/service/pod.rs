@@ -0,0 +1,405 @@
+//! [`PodOptions`] are the fields of a [`compose_spec::Service`] which apply to the whole Kubernetes
+//! pod and are merged into its [`PodSpec`].
+
+use std::{fmt::Display, net::IpAddr, time::Duration};
+
+use color_eyre::{
+    eyre::{bail, ensure, WrapErr},
+    Section,
+};
+use compose_spec::{
+    service::{Hostname, Ipc, Restart},
+    ItemOrList, ListOrMap,
+};
+use indexmap::{IndexMap, IndexSet};
+use k8s_openapi::api::core::v1::{
+    HostAlias, PodDNSConfig, PodDNSConfigOption, PodSecurityContext, PodSpec, Sysctl,
+};
+
+use super::duration_round_seconds;
+
+/// Fields from a [`compose_spec::Service`] which are merged into a [`PodSpec`], as all containers
+/// in a Kubernetes pod share them.
+pub(super) struct PodOptions {
+    pub dns: Option<ItemOrList<IpAddr>>,
+    pub dns_opt: IndexSet<String>,
+    pub dns_search: Option<ItemOrList<Hostname>>,
+    pub extra_hosts: IndexMap<Hostname, IpAddr>,
+    pub hostname: Option<Hostname>,
+    pub init: bool,
+    pub ipc: Option<Ipc>,
+    pub restart: Option<Restart>,
+    pub stop_grace_period: Option<Duration>,
+    pub sysctls: ListOrMap,
+}
+
+impl PodOptions {
+    /// Merge the options into a [`PodSpec`].
+    ///
+    /// `dns`, `dns_opt`, and `dns_search` are added to the `dnsConfig`, `extra_hosts` to the
+    /// `hostAliases`, and `sysctls` to the `securityContext`. `init` shares the process namespace of
+    /// the pod, so its infra container reaps zombie processes. `restart` is converted into the pod's
+    /// `restartPolicy`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option conflicts with a value set by another service or is not
+    /// supported.
+    pub(super) fn add_to_pod_spec(self, spec: &mut PodSpec) -> color_eyre::Result<()> {
+        let Self {
+            dns,
+            dns_opt,
+            dns_search,
+            extra_hosts,
+            hostname,
+            init,
+            ipc,
+            restart,
+            stop_grace_period,
+            sysctls,
+        } = self;
+
+        add_dns_config(spec, dns, dns_opt, dns_search)?;
+
+        let host_aliases = spec.host_aliases.get_or_insert_with(Vec::new);
+        for (hostname, ip) in extra_hosts {
+            add_host_alias(host_aliases, hostname.into(), ip.to_string())?;
+        }
+        if host_aliases.is_empty() {
+            spec.host_aliases = None;
+        }
+
+        if let Some(hostname) = hostname {
+            set_once(&mut spec.hostname, hostname.into(), "hostname")?;
+        }
+
+        if init {
+            spec.share_process_namespace = Some(true);
+        }
+
+        match ipc {
+            None | Some(Ipc::Shareable | Ipc::Service(_)) => {}
+            Some(Ipc::Other(ipc)) if ipc == "host" => spec.host_ipc = Some(true),
+            Some(Ipc::Other(ipc)) => {
+                bail!("`ipc: {ipc}` is not supported, containers in a pod share an IPC namespace");
+            }
+        }
+
+        if let Some(restart) = restart {
+            let restart_policy = match restart {
+                Restart::No => "Never",
+                Restart::Always | Restart::UnlessStopped => "Always",
+                Restart::OnFailure => "OnFailure",
+            };
+            set_once(
+                &mut spec.restart_policy,
+                restart_policy.to_owned(),
+                "restart",
+            )
+            .suggestion(
+                "a Kubernetes pod has one `restartPolicy` for all of its containers, \
+                    use the same `restart` policy for each service",
+            )?;
+        }
+
+        if let Some(stop_grace_period) = stop_grace_period {
+            let seconds = duration_round_seconds(stop_grace_period)
+                .try_into()
+                .wrap_err("`stop_grace_period` is too large")?;
+            set_once(
+                &mut spec.termination_grace_period_seconds,
+                seconds,
+                "stop_grace_period",
+            )?;
+        }
+
+        add_sysctls(spec, sysctls)
+    }
+}
+
+/// Add `dns` nameservers, `dns_opt` options, and `dns_search` domains to the `dnsConfig` of a
+/// [`PodSpec`].
+///
+/// # Errors
+///
+/// Returns an error if a DNS option conflicts with a value set by another service.
+fn add_dns_config(
+    spec: &mut PodSpec,
+    dns: Option<ItemOrList<IpAddr>>,
+    dns_opt: IndexSet<String>,
+    dns_search: Option<ItemOrList<Hostname>>,
+) -> color_eyre::Result<()> {
+    if dns.is_some() || !dns_opt.is_empty() || dns_search.is_some() {
+        let PodDNSConfig {
+            nameservers,
+            options,
+            searches,
+        } = spec.dns_config.get_or_insert_with(PodDNSConfig::default);
+
+        extend_unique(nameservers, dns.into_iter().flat_map(ItemOrList::into_list));
+        extend_unique(
+            searches,
+            dns_search.into_iter().flat_map(ItemOrList::into_list),
+        );
+
+        let options = options.get_or_insert_with(Vec::new);
+        for option in dns_opt {
+            let (name, value) = option
+                .split_once(':')
+                .map_or((option.as_str(), None), |(name, value)| (name, Some(value)));
+            ensure!(
+                add_unique(
+                    options,
+                    PodDNSConfigOption {
+                        name: Some(name.to_owned()),
+                        value: value.map(str::to_owned),
+                    },
+                    |option| option.name.as_deref() == Some(name),
+                    |option| option.value.as_deref() == value,
+                ),
+                "`dns_opt` `{option}` conflicts with a value set by another service"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Add `sysctls` to the `securityContext` of a [`PodSpec`].
+///
+/// # Errors
+///
+/// Returns an error if the `sysctls` could not be converted or conflict with a value set by another
+/// service.
+fn add_sysctls(spec: &mut PodSpec, sysctls: ListOrMap) -> color_eyre::Result<()> {
+    if !sysctls.is_empty() {
+        let sysctls = sysctls.into_map().wrap_err("error converting `sysctls`")?;
+        let security_context = spec
+            .security_context
+            .get_or_insert_with(PodSecurityContext::default)
+            .sysctls
+            .get_or_insert_with(Vec::new);
+        for (name, value) in sysctls {
+            let name = String::from(name);
+            let value = value.map(String::from).unwrap_or_default();
+            ensure!(
+                add_unique(
+                    security_context,
+                    Sysctl {
+                        name: name.clone(),
+                        value: value.clone(),
+                    },
+                    |sysctl| sysctl.name == name,
+                    |sysctl| sysctl.value == value,
+                ),
+                "`sysctls` `{name}={value}` conflicts with a value set by another service"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Set `option` to `value`, unless another service already set it to a different value.
+///
+/// # Errors
+///
+/// Returns an error if `option` is already set to a different value.
+fn set_once<T: PartialEq + Display>(
+    option: &mut Option<T>,
+    value: T,
+    field: &str,
+) -> color_eyre::Result<()> {
+    if let Some(current) = option {
+        ensure!(
+            *current == value,
+            "`{field}` `{value}` conflicts with `{current}` set by another service"
+        );
+    } else {
+        *option = Some(value);
+    }
+    Ok(())
+}
+
+/// Extend the `list` with the `values` not already in it.
+fn extend_unique<T: Display>(list: &mut Option<Vec<String>>, values: impl IntoIterator<Item = T>) {
+    for value in values {
+        let value = value.to_string();
+        let list = list.get_or_insert_with(Vec::new);
+        if !list.contains(&value) {
+            list.push(value);
+        }
+    }
+}
+
+/// Add `item` to `list`, unless an item with the same key, determined by `same_key`, is already in
+/// it.
+///
+/// Returns `false` if an item with the same key does not have the same value, as determined by
+/// `same_value`.
+fn add_unique<T>(
+    list: &mut Vec<T>,
+    item: T,
+    same_key: impl Fn(&T) -> bool,
+    same_value: impl Fn(&T) -> bool,
+) -> bool {
+    if let Some(current) = list.iter().find(|current| same_key(current)) {
+        same_value(current)
+    } else {
+        list.push(item);
+        true
+    }
+}
+
+/// Add a `hostname` for the `ip` to the [`HostAlias`]es of a [`PodSpec`].
+///
+/// # Errors
+///
+/// Returns an error if the `hostname` is already an alias for a different IP address.
+fn add_host_alias(
+    host_aliases: &mut Vec<HostAlias>,
+    hostname: String,
+    ip: String,
+) -> color_eyre::Result<()> {
+    for HostAlias {
+        hostnames,
+        ip: alias_ip,
+    } in &*host_aliases
+    {
+        if hostnames.iter().flatten().any(|alias| *alias == hostname) {
+            let alias_ip = alias_ip.as_deref().unwrap_or_default();
+            ensure!(
+                alias_ip == ip,
+                "`extra_hosts` `{hostname}` with IP address `{ip}` conflicts with `{alias_ip}` \
+                    set by another service"
+            );
+            return Ok(());
+        }
+    }
+
+    if let Some(hostnames) = host_aliases
+        .iter_mut()
+        .find(|alias| alias.ip.as_ref() == Some(&ip))
+        .map(|alias| alias.hostnames.get_or_insert_with(Vec::new))
+    {
+        hostnames.push(hostname);
+    } else {
+        host_aliases.push(HostAlias {
+            hostnames: Some(vec![hostname]),
+            ip: Some(ip),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn pod_options(yaml: &str) -> PodOptions {
+        let compose_spec::Service {
+            dns,
+            dns_opt,
+            dns_search,
+            extra_hosts,
+            hostname,
+            init,
+            ipc,
+            restart,
+            stop_grace_period,
+            sysctls,
+            ..
+        } = serde_yaml::from_str(yaml).unwrap();
+        PodOptions {
+            dns,
+            dns_opt,
+            dns_search,
+            extra_hosts,
+            hostname,
+            init,
+            ipc,
+            restart,
+            stop_grace_period,
+            sysctls,
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut spec = PodSpec::default();
+        pod_options(
+            "dns: 1.1.1.1\n\
+            dns_opt: ['ndots:2']\n\
+            extra_hosts: ['db=10.0.0.2']\n\
+            hostname: app\n\
+            init: true\n\
+            restart: unless-stopped\n\
+            stop_grace_period: 20s\n\
+            sysctls: {net.core.somaxconn: 1024}\n",
+        )
+        .add_to_pod_spec(&mut spec)
+        .unwrap();
+        pod_options(
+            "dns: [1.1.1.1, 8.8.8.8]\n\
+            extra_hosts: ['cache=10.0.0.2']\n\
+            hostname: app\n\
+            ipc: host\n\
+            restart: always\n\
+            sysctls: ['net.core.somaxconn=1024']\n",
+        )
+        .add_to_pod_spec(&mut spec)
+        .unwrap();
+
+        let dns_config = spec.dns_config.unwrap();
+        assert_eq!(dns_config.nameservers.unwrap(), ["1.1.1.1", "8.8.8.8"]);
+        assert_eq!(
+            dns_config.options.unwrap(),
+            [PodDNSConfigOption {
+                name: Some("ndots".to_owned()),
+                value: Some("2".to_owned()),
+            }]
+        );
+        assert_eq!(
+            spec.host_aliases.unwrap(),
+            [HostAlias {
+                hostnames: Some(vec!["db".to_owned(), "cache".to_owned()]),
+                ip: Some("10.0.0.2".to_owned()),
+            }]
+        );
+        assert_eq!(spec.hostname.unwrap(), "app");
+        assert_eq!(spec.share_process_namespace, Some(true));
+        assert_eq!(spec.host_ipc, Some(true));
+        assert_eq!(spec.restart_policy.unwrap(), "Always");
+        assert_eq!(spec.termination_grace_period_seconds, Some(20));
+        assert_eq!(
+            spec.security_context.unwrap().sysctls.unwrap(),
+            [Sysctl {
+                name: "net.core.somaxconn".to_owned(),
+                value: "1024".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn conflict() {
+        let mut spec = PodSpec::default();
+        pod_options("hostname: one\nextra_hosts: ['db=10.0.0.2']\n")
+            .add_to_pod_spec(&mut spec)
+            .unwrap();
+        assert!(pod_options("hostname: two\n")
+            .add_to_pod_spec(&mut spec)
+            .is_err());
+        assert!(pod_options("extra_hosts: ['db=10.0.0.3']\n")
+            .add_to_pod_spec(&mut spec)
+            .is_err());
+
+        pod_options("restart: on-failure\n")
+            .add_to_pod_spec(&mut spec)
+            .unwrap();
+        assert!(pod_options("restart: 'no'\n")
+            .add_to_pod_spec(&mut spec)
+            .is_err());
+    }
+}
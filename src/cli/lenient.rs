@@ -0,0 +1,159 @@
+//! Support for `podlet compose --lenient`, where unsupported service fields are dropped and
+//! reported instead of returning an error.
+
+use std::fmt::{self, Display, Formatter};
+
+use color_eyre::{eyre::eyre, Section};
+use compose_spec::Identifier;
+
+/// A compose service field which is not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedField {
+    /// Name of the field, e.g. `build`.
+    field: &'static str,
+
+    /// Why the field is not supported.
+    reason: String,
+
+    /// Suggested alternative to the field, if any.
+    suggestion: Option<&'static str>,
+}
+
+impl UnsupportedField {
+    /// Create an [`UnsupportedField`] for `field` with the `reason` it is not supported.
+    pub fn new(field: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            field,
+            reason: reason.into(),
+            suggestion: None,
+        }
+    }
+
+    /// Add a suggested alternative to using the field.
+    #[must_use]
+    pub const fn with_suggestion(mut self, suggestion: &'static str) -> Self {
+        self.suggestion = Some(suggestion);
+        self
+    }
+
+    /// Convert into an error.
+    pub fn into_report(self) -> color_eyre::Report {
+        let Self {
+            field: _,
+            reason,
+            suggestion,
+        } = self;
+
+        let report = eyre!(reason);
+        if let Some(suggestion) = suggestion {
+            report.suggestion(suggestion)
+        } else {
+            report
+        }
+    }
+}
+
+/// Ensure there are no `unsupported_fields`, or, if `dropped` is [`Some`], add them to it instead.
+///
+/// # Errors
+///
+/// Returns an error for the first unsupported field if `dropped` is [`None`].
+pub fn ensure_empty_or_drop(
+    unsupported_fields: Vec<UnsupportedField>,
+    dropped: Option<&mut Vec<UnsupportedField>>,
+) -> color_eyre::Result<()> {
+    if let Some(dropped) = dropped {
+        dropped.extend(unsupported_fields);
+        Ok(())
+    } else {
+        unsupported_fields
+            .into_iter()
+            .next()
+            .map_or(Ok(()), |field| Err(field.into_report()))
+    }
+}
+
+/// Unsupported fields which were dropped from compose services.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LenientReport {
+    /// Dropped fields paired with their service's name.
+    dropped: Vec<(Identifier, UnsupportedField)>,
+}
+
+impl LenientReport {
+    /// Add the `fields` dropped from `service` to the report.
+    pub fn add(
+        &mut self,
+        service: &Identifier,
+        fields: impl IntoIterator<Item = UnsupportedField>,
+    ) {
+        self.dropped
+            .extend(fields.into_iter().map(|field| (service.clone(), field)));
+    }
+
+    /// Returns `true` if no fields were dropped.
+    pub fn is_empty(&self) -> bool {
+        self.dropped.is_empty()
+    }
+}
+
+impl Display for LenientReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "dropped {} unsupported field(s) from compose services:",
+            self.dropped.len()
+        )?;
+        for (
+            service,
+            UnsupportedField {
+                field,
+                reason,
+                suggestion,
+            },
+        ) in &self.dropped
+        {
+            write!(f, "\n  - service `{service}`, field `{field}`: {reason}")?;
+            if let Some(suggestion) = suggestion {
+                write!(f, "\n    suggestion: {suggestion}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict() {
+        let fields = vec![UnsupportedField::new("build", "`build` is not supported")];
+        let error = ensure_empty_or_drop(fields, None).unwrap_err();
+        assert_eq!(error.to_string(), "`build` is not supported");
+
+        assert!(ensure_empty_or_drop(Vec::new(), None).is_ok());
+    }
+
+    #[test]
+    fn report() {
+        let mut dropped = Vec::new();
+        let fields = vec![
+            UnsupportedField::new("build", "`build` is not supported"),
+            UnsupportedField::new("devices", "devices are not supported")
+                .with_suggestion("try using a bind mount instead"),
+        ];
+        ensure_empty_or_drop(fields, Some(&mut dropped)).unwrap();
+
+        let mut report = LenientReport::default();
+        report.add(&Identifier::new("web").unwrap(), dropped);
+        assert_eq!(
+            report.to_string(),
+            "dropped 2 unsupported field(s) from compose services:\n  \
+                - service `web`, field `build`: `build` is not supported\n  \
+                - service `web`, field `devices`: devices are not supported\n    \
+                suggestion: try using a bind mount instead"
+        );
+    }
+}
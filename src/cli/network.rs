@@ -150,6 +150,7 @@ impl From<Create> for crate::quadlet::Network {
             ip_range: value.ip_range,
             ipv6: value.ipv6,
             label: value.label,
+            network_name: None,
             options: value.opt,
             podman_args: (!podman_args.is_empty()).then_some(podman_args),
             subnet: value.subnet,
@@ -1,28 +1,88 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    path::PathBuf,
+};
 
 use clap::{Args, ValueEnum};
-use compose_spec::service::Restart;
+use compose_spec::service::{deploy::RestartCondition, Restart};
+use serde::Serialize;
 
-#[derive(Args, Default, Debug, Clone, PartialEq, Eq)]
+use crate::{quadlet::ExtraOptions, serde::quadlet::quote_spaces_join_space};
+
+#[derive(Serialize, Args, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
 pub struct Service {
     /// Configure if and when the service should be restarted
     #[arg(long, value_name = "POLICY")]
     restart: Option<RestartConfig>,
+
+    /// Time to sleep before restarting the service.
+    #[arg(skip)]
+    restart_sec: Option<String>,
+
+    /// Environment variables set for the service's processes.
+    #[arg(skip)]
+    #[serde(
+        serialize_with = "quote_spaces_join_space",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    environment: Vec<String>,
+
+    /// Files to read environment variables for the service's processes from.
+    #[arg(skip)]
+    environment_file: Vec<PathBuf>,
+
+    /// Commands to execute before the service's main process.
+    #[arg(skip)]
+    exec_start_pre: Vec<String>,
+
+    /// Additional options added to the end of the section.
+    #[arg(skip)]
+    #[serde(skip)]
+    extra: ExtraOptions,
 }
 
 impl Service {
     pub fn is_empty(&self) -> bool {
         *self == Self::default()
     }
+
+    /// Set `Restart=` from a compose `deploy.restart_policy.condition`.
+    pub fn set_restart_condition(&mut self, condition: RestartCondition) {
+        self.restart = Some(condition.into());
+    }
+
+    /// Set `RestartSec=`, the time to sleep before restarting the service, as a systemd time span.
+    pub fn set_restart_sec(&mut self, restart_sec: String) {
+        self.restart_sec = Some(restart_sec);
+    }
+
+    /// Add an `Environment=` variable assignment, `name=value`, to the service.
+    pub fn add_environment(&mut self, name: &str, value: &str) {
+        self.environment.push(format!("{name}={value}"));
+    }
+
+    /// Add an `EnvironmentFile=` to the service.
+    pub fn add_environment_file(&mut self, path: PathBuf) {
+        self.environment_file.push(path);
+    }
+
+    /// Add options to the end of the section.
+    pub fn extend_extra(&mut self, options: impl IntoIterator<Item = (String, String)>) {
+        self.extra.extend(options);
+    }
+
+    /// Add an `ExecStartPre=` command to the service.
+    pub fn add_exec_start_pre(&mut self, command: String) {
+        self.exec_start_pre.push(command);
+    }
 }
 
 impl Display for Service {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        writeln!(f, "[Service]")?;
-        if let Some(restart) = self.restart.and_then(|restart| restart.to_possible_value()) {
-            writeln!(f, "Restart={}", restart.get_name())?;
-        }
-        Ok(())
+        let service = crate::serde::quadlet::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&service)?;
+        self.extra.fmt(f)
     }
 }
 
@@ -30,6 +90,7 @@ impl From<RestartConfig> for Service {
     fn from(restart: RestartConfig) -> Self {
         Self {
             restart: Some(restart),
+            ..Self::default()
         }
     }
 }
@@ -43,7 +104,8 @@ impl From<Restart> for Service {
 /// Possible service restart configurations
 ///
 /// From [systemd.service](https://www.freedesktop.org/software/systemd/man/systemd.service.html#Restart=)
-#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
 enum RestartConfig {
     No,
     OnSuccess,
@@ -64,3 +126,38 @@ impl From<Restart> for RestartConfig {
         }
     }
 }
+
+impl From<RestartCondition> for RestartConfig {
+    fn from(value: RestartCondition) -> Self {
+        match value {
+            RestartCondition::None => Self::No,
+            RestartCondition::OnFailure => Self::OnFailure,
+            RestartCondition::Any => Self::Always,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_display() {
+        let service = Service::from(RestartConfig::OnFailure);
+        assert_eq!(service.to_string(), "[Service]\nRestart=on-failure\n");
+    }
+
+    #[test]
+    fn environment_display() {
+        let mut service = Service::default();
+        service.add_environment("ONE", "1");
+        service.add_environment("TWO", "two words");
+        service.add_environment_file("/path/to/.env".into());
+        assert_eq!(
+            service.to_string(),
+            "[Service]\n\
+            Environment=ONE=1 \"TWO=two words\"\n\
+            EnvironmentFile=/path/to/.env\n"
+        );
+    }
+}
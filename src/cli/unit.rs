@@ -8,7 +8,7 @@ use color_eyre::{
 use compose_spec::service::{Condition, Dependency};
 use serde::Serialize;
 
-use crate::serde::quadlet::quote_spaces_join_space;
+use crate::{quadlet::ExtraOptions, serde::quadlet::quote_spaces_join_space};
 
 // Common systemd unit options
 // From [systemd.unit](https://www.freedesktop.org/software/systemd/man/systemd.unit.html)
@@ -83,6 +83,19 @@ pub struct Unit {
         skip_serializing_if = "Vec::is_empty"
     )]
     after: Vec<String>,
+
+    /// Interval in which the unit may start at most `start_limit_burst` times.
+    #[arg(skip)]
+    start_limit_interval_sec: Option<String>,
+
+    /// Number of times the unit may start within `start_limit_interval_sec`.
+    #[arg(skip)]
+    start_limit_burst: Option<u64>,
+
+    /// Additional options added to the end of the section.
+    #[arg(skip)]
+    #[serde(skip)]
+    extra: ExtraOptions,
 }
 
 impl Unit {
@@ -95,6 +108,9 @@ impl Unit {
             binds_to,
             before,
             after,
+            start_limit_interval_sec,
+            start_limit_burst,
+            extra,
         } = self;
 
         description.is_none()
@@ -103,6 +119,22 @@ impl Unit {
             && binds_to.is_empty()
             && before.is_empty()
             && after.is_empty()
+            && start_limit_interval_sec.is_none()
+            && start_limit_burst.is_none()
+            && extra.is_empty()
+    }
+
+    /// Add options to the end of the section.
+    pub fn extend_extra(&mut self, options: impl IntoIterator<Item = (String, String)>) {
+        self.extra.extend(options);
+    }
+
+    /// Limit the unit to starting at most `burst` times within the `interval`, a systemd time span.
+    ///
+    /// Converts to "StartLimitIntervalSec=INTERVAL" and "StartLimitBurst=BURST".
+    pub fn set_start_limit(&mut self, burst: u64, interval: String) {
+        self.start_limit_burst = Some(burst);
+        self.start_limit_interval_sec = Some(interval);
     }
 
     /// Add a compose [`Service`](compose_spec::Service) [`Dependency`] to the unit.
@@ -161,6 +193,7 @@ fn condition_eyre(condition: Condition, option: &str, section: &str) -> eyre::Re
 impl Display for Unit {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         let unit = crate::serde::quadlet::to_string(self).map_err(|_| fmt::Error)?;
-        f.write_str(&unit)
+        f.write_str(&unit)?;
+        self.extra.fmt(f)
     }
 }
@@ -1,4 +1,5 @@
 pub mod container;
+mod extra;
 mod globals;
 pub mod image;
 mod install;
@@ -20,6 +21,7 @@ use thiserror::Error;
 
 pub use self::{
     container::Container,
+    extra::ExtraOptions,
     globals::Globals,
     image::Image,
     install::Install,
@@ -0,0 +1,58 @@
+use std::fmt::{self, Display, Formatter};
+
+/// Additional options, which are not otherwise supported, added to the end of a section of a
+/// Quadlet file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExtraOptions(Vec<(String, String)>);
+
+impl ExtraOptions {
+    /// Returns `true` if there are no extra options.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add an option, which converts to "KEY=VALUE".
+    pub fn push(&mut self, key: String, value: String) {
+        self.0.push((key, value));
+    }
+}
+
+impl Extend<(String, String)> for ExtraOptions {
+    fn extend<T: IntoIterator<Item = (String, String)>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for ExtraOptions {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Display for ExtraOptions {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (key, value) in &self.0 {
+            writeln!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let mut extra = ExtraOptions::default();
+        extra.push("AutoUpdate".into(), "registry".into());
+        extra.push("PodmanArgs".into(), "--one --two".into());
+        assert_eq!(
+            extra.to_string(),
+            "AutoUpdate=registry\nPodmanArgs=--one --two\n"
+        );
+    }
+}
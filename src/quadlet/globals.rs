@@ -5,7 +5,7 @@ use std::{
 
 use serde::Serialize;
 
-use super::{Downgrade, DowngradeError, HostPaths, PodmanVersion};
+use super::{Downgrade, DowngradeError, ExtraOptions, HostPaths, PodmanVersion};
 
 /// Global Quadlet options that apply to all resource types.
 #[derive(Serialize, Debug, Default, Clone, PartialEq)]
@@ -16,6 +16,10 @@ pub struct Globals {
 
     /// A list of arguments passed directly after `podman`.
     pub global_args: Option<String>,
+
+    /// Additional options added to the end of the resource's section.
+    #[serde(skip)]
+    pub extra: ExtraOptions,
 }
 
 impl Downgrade for Globals {
@@ -54,7 +58,8 @@ impl Display for Globals {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         let globals =
             crate::serde::quadlet::to_string_no_table_name(self).map_err(|_| fmt::Error)?;
-        f.write_str(&globals)
+        f.write_str(&globals)?;
+        self.extra.fmt(f)
     }
 }
 
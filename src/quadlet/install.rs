@@ -4,7 +4,9 @@ use serde::Serialize;
 
 use crate::serde::quadlet::quote_spaces_join_space;
 
-#[derive(Serialize, Debug, Clone, PartialEq)]
+use super::ExtraOptions;
+
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "PascalCase")]
 pub struct Install {
     /// Add weak parent dependencies to the unit.
@@ -20,11 +22,16 @@ pub struct Install {
         skip_serializing_if = "Vec::is_empty"
     )]
     pub required_by: Vec<String>,
+
+    /// Additional options added to the end of the section.
+    #[serde(skip)]
+    pub extra: ExtraOptions,
 }
 
 impl Display for Install {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         let install = crate::serde::quadlet::to_string(self).map_err(|_| fmt::Error)?;
-        f.write_str(&install)
+        f.write_str(&install)?;
+        self.extra.fmt(f)
     }
 }
@@ -55,6 +55,12 @@ pub struct Network {
     )]
     pub label: Vec<String>,
 
+    /// The name of the Podman network.
+    ///
+    /// If not set, the default value is `systemd-%N`.
+    #[allow(clippy::struct_field_names)]
+    pub network_name: Option<String>,
+
     /// Set driver specific options.
     pub options: Vec<String>,
 
@@ -82,6 +88,16 @@ impl Network {
 
 impl Downgrade for Network {
     fn downgrade(&mut self, version: PodmanVersion) -> Result<(), DowngradeError> {
+        if version < PodmanVersion::V4_8 {
+            if let Some(network_name) = self.network_name.take() {
+                return Err(DowngradeError::Option {
+                    quadlet_option: "NetworkName",
+                    value: network_name,
+                    supported_version: PodmanVersion::V4_8,
+                });
+            }
+        }
+
         if version < PodmanVersion::V4_7 {
             for dns in std::mem::take(&mut self.dns) {
                 self.push_arg("dns", &dns);
@@ -127,7 +143,6 @@ impl TryFrom<compose_spec::Network> for Network {
 
         let unsupported_options = [
             ("attachable", !attachable),
-            ("name", name.is_none()),
             ("ipam.options", ipam_options.is_empty()),
         ];
         for (option, not_present) in unsupported_options {
@@ -148,6 +163,7 @@ impl TryFrom<compose_spec::Network> for Network {
             ipam_driver,
             internal,
             label: labels.into_list().into_iter().collect(),
+            network_name: name,
             ..Self::default()
         };
 
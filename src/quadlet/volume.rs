@@ -51,6 +51,12 @@ pub struct Volume {
 
     /// The host (numeric) UID, or user name to use as the owner for the volume.
     pub user: Option<String>,
+
+    /// The name of the Podman volume.
+    ///
+    /// If not set, the default value is `systemd-%N`.
+    #[allow(clippy::struct_field_names)]
+    pub volume_name: Option<String>,
 }
 
 impl HostPaths for Volume {
@@ -76,6 +82,14 @@ impl Volume {
 impl Downgrade for Volume {
     fn downgrade(&mut self, version: PodmanVersion) -> Result<(), DowngradeError> {
         if version < PodmanVersion::V4_8 {
+            if let Some(volume_name) = self.volume_name.take() {
+                return Err(DowngradeError::Option {
+                    quadlet_option: "VolumeName",
+                    value: volume_name,
+                    supported_version: PodmanVersion::V4_8,
+                });
+            }
+
             if let Some(driver) = self.driver.take() {
                 self.push_arg("driver", &driver);
             }
@@ -111,7 +125,6 @@ impl TryFrom<compose_spec::Volume> for Volume {
             extensions,
         }: compose_spec::Volume,
     ) -> Result<Self, Self::Error> {
-        ensure!(name.is_none(), "`name` is not supported");
         ensure!(
             extensions.is_empty(),
             "compose extensions are not supported"
@@ -133,6 +146,7 @@ impl TryFrom<compose_spec::Volume> for Volume {
         Ok(Self {
             driver,
             label: labels.into_list().into_iter().collect(),
+            volume_name: name,
             ..options.into()
         })
     }